@@ -0,0 +1,44 @@
+//! Compare fixed-point and floating-point error diffusion on the same grayscale image.
+//! Run with `cargo bench --bench fixed`.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use dither::ditherer::{fixed, ATKINSON, FLOYD_STEINBERG, STUCKI};
+use dither::prelude::*;
+
+const WIDTH: usize = 512;
+
+fn gradient() -> Vec<u8> {
+    (0..WIDTH * WIDTH)
+        .map(|i| ((i % WIDTH + i / WIDTH) / 4 % 256) as u8)
+        .collect()
+}
+
+fn fixed_vs_float(c: &mut Criterion) {
+    let gray = gradient();
+    let img: Img<f64> = Img::new(gray.iter().map(|&x| f64::from(x)), WIDTH as u32).unwrap();
+    let quantize = dither::create_quantize_n_bits_func(1).unwrap();
+    let fixed_quantize = fixed::quantize_n_bits(1).unwrap();
+
+    let mut group = c.benchmark_group("dither 512x512 gray to 1 bit");
+    for (name, kernel) in [
+        ("floyd", FLOYD_STEINBERG),
+        ("atkinson", ATKINSON),
+        ("stucki", STUCKI),
+    ] {
+        let fixed_kernel = kernel.to_fixed().unwrap();
+        let mut scratch = vec![0; fixed_kernel.scratch_len(WIDTH, 1)];
+        group.bench_function(BenchmarkId::new("fixed", name), |b| {
+            b.iter(|| {
+                let mut buf = gray.clone();
+                fixed_kernel.dither(&mut buf, WIDTH, 1, &mut scratch, &fixed_quantize);
+                buf
+            })
+        });
+        group.bench_function(BenchmarkId::new("f64", name), |b| {
+            b.iter(|| kernel.dither(img.clone(), &quantize))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, fixed_vs_float);
+criterion_main!(benches);
@@ -0,0 +1,88 @@
+//! Search for error-diffusion kernels that minimize a perceptual error metric over a set of training images.
+//! See [dither::ditherer::optimize].
+use clap::Parser;
+use dither::ditherer::optimize;
+use dither::prelude::*;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[clap(name = "dither-optimize")]
+#[clap(version = "1.3.8", author = "Efron Licht (efron.python@gmail.com)")]
+/// Search for error-diffusion weights that minimize a perceptual error metric over a set of training images,
+/// and print the result as a `Ditherer::new` kernel.
+struct OptimizeOpt {
+    /// Provide verbose debug information. Default is false.
+    #[clap(short = 'v', long = "verbose")]
+    verbose: bool,
+
+    /// Training images. They're converted to grayscale before dithering.
+    #[clap(name = "training", required = true)]
+    training: Vec<PathBuf>,
+
+    /// Ditherer to start from; the optimized kernel keeps its offsets. See `dither --help` for options.
+    #[clap(short = 'd', long = "dither", default_value = "floyd")]
+    ditherer: Ditherer<'static>,
+
+    /// Color depth to quantize to while scoring. Must be between 1 and 7.
+    #[clap(long = "depth", default_value = "1")]
+    bit_depth: u8,
+
+    /// Number of search steps.
+    #[clap(short = 'n', long = "iterations", default_value = "1000")]
+    iterations: usize,
+
+    /// Multiply the starting weights by this factor, for finer-grained weights. Must be at least 1.
+    #[clap(long = "resolution", default_value = "1")]
+    resolution: NonZeroU32,
+
+    /// Seed for the search.
+    #[clap(long = "seed", default_value = "1")]
+    seed: u64,
+}
+
+fn main() {
+    let opts = OptimizeOpt::try_parse().expect("could not parse options");
+    if let Err(err) = _main(&opts) {
+        eprintln!("{}", err);
+
+        std::process::exit(1)
+    } else {
+        std::process::exit(0)
+    }
+}
+
+fn _main(opts: &OptimizeOpt) -> Result<()> {
+    let quantize = dither::create_quantize_n_bits_func(opts.bit_depth)?;
+    let mut training = Vec::with_capacity(opts.training.len());
+    for path in &opts.training {
+        if opts.verbose {
+            eprintln!("loading training image \"{}\"", path.display());
+        }
+        training.push(
+            Img::<RGB<f64>>::load(path)?
+                .convert_with(|rgb| rgb.to_chroma_corrected_black_and_white()),
+        );
+    }
+
+    if opts.verbose {
+        eprintln!(
+            "starting score for {}: {}\noptimizing...",
+            opts.ditherer,
+            optimize::score(&opts.ditherer, &training, &quantize)
+        );
+    }
+    let (kernel, score) = optimize::optimize(
+        &opts.ditherer,
+        &training,
+        &quantize,
+        opts.iterations,
+        opts.resolution,
+        opts.seed,
+    );
+    if opts.verbose {
+        eprintln!("optimized score: {}", score);
+    }
+    println!("{}", kernel);
+    Ok(())
+}
@@ -0,0 +1,600 @@
+//! The command-line tool, as a library: `src/bin/dither.rs` just calls [main].
+//!
+//! Downstream crates can [register][crate::ditherer::registry::register] their own algorithms, then call [main]
+//! from their own binary to get the same tool with their algorithms in `--dither` and `--help`.
+use crate::color::palette::{self, Search};
+use crate::color::space::{InSpace, Space};
+use crate::color::subpixel;
+use crate::ditherer::sequence::Sequence;
+use crate::ditherer::temporal;
+use crate::ditherer::{
+    fixed, registry,
+    registry::{Algorithm, DynDither},
+    Cancel,
+};
+use crate::edges;
+use crate::pixel::Channel;
+use crate::prelude::*;
+use crate::quantize::{BitDepth, CloneQuantizer, Float, PerChannel, Quantizer, SingleColor};
+use clap::{CommandFactory, FromArgMatches};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// parse the command-line arguments and run, listing every algorithm in the [global][registry::global] registry in `--help`.
+/// exits the process when finished.
+pub fn main() {
+    let algorithms = registry::global()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .help();
+    let matches = Opt::command()
+        .mut_arg("ditherer", |arg| {
+            arg.help("Ditherering algorithm to use").long_help(format!(
+                "Ditherering algorithm to use. Algorithms with parameters are selected as \
+                     \"name:parameter=value,parameter=value\". Options are\n{}",
+                algorithms.trim_end()
+            ))
+        })
+        .get_matches();
+    let opts = Opt::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    if let Err(err) = run(&opts) {
+        eprintln!("{}", err);
+
+        std::process::exit(1)
+    } else {
+        std::process::exit(0)
+    }
+}
+
+/// run the tool with the given options.
+pub fn run(opts: &Opt) -> Result<()> {
+    let (input, output) = (&opts.input, opts.output_path()?);
+    if opts.verbose {
+        eprintln!(
+            concat!(
+                "running dither in VERBOSE mode:\n\t",
+                "INPUT: {input}\n\t",
+                "OUTPUT: {output}\n\t",
+                "DITHERER: {dither}\n\t",
+                "BIT_DEPTH: {depth}\n\t",
+                "COLOR_MODE: {mode}\n\t",
+                "SMART: {smart:?}\n\t",
+                "DISTANCE: {distance:?}\n\t",
+                "EXTRACT: {extract:?}\n\t",
+                "SUBSET: {subset:?}\n\t",
+                "SAVE_PALETTE: {save_palette:?}\n\t",
+                "SPACE: {space:?}\n\t",
+                "LUT: {lut}\n\t",
+                "SUBPIXEL: {subpixel:?}\n\t",
+                "FRAMES: {frames:?}\n\t",
+                "PACKED: {packed}\n\t",
+                "SEQUENCE: {sequence:?}\n\t",
+                "EDGES: {edges:?}\n\t",
+                "THREADS: {threads:?}\n\t",
+                "FIXED: {fixed}\n\t",
+                "PRECISION: {precision:?}"
+            ),
+            input = input.display(),
+            output = output.display(),
+            dither = opts.ditherer,
+            depth = opts.bit_depth,
+            mode = opts.color_mode,
+            smart = opts.flat_tolerance,
+            distance = opts.distance,
+            extract = opts.extract,
+            subset = opts.subset,
+            save_palette = opts.save_palette,
+            space = opts.space,
+            lut = opts.lut,
+            subpixel = opts.subpixel,
+            frames = opts.frames,
+            packed = opts.packed,
+            sequence = opts.sequence,
+            edges = opts.edges,
+            threads = opts.threads,
+            fixed = opts.fixed,
+            precision = opts.precision,
+        );
+    }
+    let extracted;
+    let opts = match opts.color_mode {
+        color::Mode::Auto { colors } if !opts.fixed => {
+            extracted = with_extracted_palette(opts, colors)?;
+            &extracted
+        }
+        color::Mode::Auto { .. } => opts,
+        _ if opts.extract.is_some() => return Err(Error::ExtractRequiresAuto),
+        _ => opts,
+    };
+    let subset;
+    let opts = match (&opts.color_mode, opts.subset) {
+        (color::Mode::Palette { palette, name }, Some(colors)) => {
+            subset = with_palette_subset(opts, palette, name, colors.get())?;
+            &subset
+        }
+        (_, Some(_)) => return Err(Error::SubsetRequiresPalette),
+        _ => opts,
+    };
+    if let Some(path) = &opts.save_palette {
+        let color::Mode::Palette { palette, name } = &opts.color_mode else {
+            return Err(Error::SavePaletteRequiresPalette);
+        };
+        palette::format::save(palette, name, path).map_err(|err| Error::output(err, path))?;
+        if opts.verbose {
+            eprintln!("palette saved to \"{}\".", path.display());
+        }
+    }
+    if let Some(threshold) = opts.edges {
+        // at 0 or below, every pixel is an edge.
+        if threshold <= 0. || threshold.is_nan() {
+            return Err(Error::BadEdgeThreshold(threshold));
+        }
+        if let color::Mode::Palette { palette, .. } = &opts.color_mode {
+            if !palette.contains(&opts.edge_color) {
+                return Err(Error::EdgeColorNotInPalette(opts.edge_color));
+            }
+        }
+    }
+    if opts.subpixel.is_some() && opts.color_mode != color::Mode::Color {
+        return Err(Error::SubpixelRequiresColor);
+    }
+    if opts.distance.is_some() && !matches!(opts.color_mode, color::Mode::Palette { .. }) {
+        return Err(Error::DistanceRequiresPalette);
+    }
+    if opts.space.is_some() && !matches!(opts.color_mode, color::Mode::Palette { .. }) {
+        return Err(Error::SpaceRequiresPalette);
+    }
+    if opts.lut && !matches!(opts.color_mode, color::Mode::Palette { .. }) {
+        return Err(Error::LutRequiresPalette);
+    }
+    if opts.frames.is_some() && opts.fixed {
+        return Err(Error::FramesIncompatibleWithFixed);
+    }
+    if opts.sequence.is_some() && opts.fixed {
+        return Err(Error::SequenceIncompatibleWithFixed);
+    }
+    if opts.sequence.is_some() && opts.frames.is_some() {
+        return Err(Error::SequenceIncompatibleWithFrames);
+    }
+    if opts.packed
+        && !matches!(
+            opts.color_mode,
+            color::Mode::BlackAndWhite | color::Mode::Color
+        )
+    {
+        return Err(Error::PackedRequiresBitDepthMode);
+    }
+    let threads = opts.threads.unwrap_or_else(|| {
+        std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+    });
+    if let Some(tolerance) = opts.sequence {
+        return match opts.precision {
+            Precision::F32 => dither_sequence::<f32>(opts, &output, tolerance, threads),
+            Precision::F64 => dither_sequence::<f64>(opts, &output, tolerance, threads),
+        };
+    }
+    let output_imgs = if opts.fixed {
+        vec![dither_fixed(opts, load(opts, &opts.input)?)?]
+    } else {
+        match opts.precision {
+            Precision::F32 => dither_float::<f32>(opts, load(opts, &opts.input)?, threads, None)?,
+            Precision::F64 => dither_float::<f64>(opts, load(opts, &opts.input)?, threads, None)?,
+        }
+    };
+    finish(opts, &output, output_imgs)
+}
+
+fn load<N: From<u8>>(opts: &Opt, path: &Path) -> Result<Img<RGB<N>>> {
+    let img = Img::<RGB<N>>::load(path)?;
+    if opts.verbose {
+        eprintln!("image loaded from \"{}\".\ndithering...", path.display())
+    }
+    Ok(img)
+}
+
+/// a floating-point working representation for the image. See [Precision].
+trait Working: Channel + Float + Send + Sync + From<u8> + 'static {}
+
+impl Working for f32 {}
+
+impl Working for f64 {}
+
+/// the `--sequence` state between frames, for whichever pixel type the color mode dithers.
+struct Sequences<S> {
+    gray: Sequence<S>,
+    rgb: Sequence<RGB<S>>,
+    space: Sequence<[S; 3]>,
+    /// the palette quantizer, built for the first frame and reused for the rest: every frame has the same palette,
+    /// and a `--lut` is costly to build.
+    nearest: Option<palette::Nearest>,
+}
+
+/// dither `img` with `quantize`: as the next frame of the `--sequence`, if there is one,
+/// otherwise into `--frames` frames if set. See [Sequence] and [temporal].
+fn dither_with<P, Q>(
+    opts: &Opt,
+    img: Img<P>,
+    quantize: Q,
+    sequence: Option<&mut Sequence<P>>,
+    threads: usize,
+) -> Vec<Img<P>>
+where
+    P: Pixel + Send + Sync,
+    P::Channel: Into<f64>,
+    Q: Quantizer<P> + Clone + Send,
+    dyn Algorithm: DynDither<P>,
+{
+    match (sequence, opts.frames) {
+        (Some(sequence), _) => vec![sequence.dither(img, quantize, |img, coherent| {
+            dither_dyn(opts, img, &coherent, threads)
+        })],
+        (None, Some(n)) => temporal::dither_frames(&img, n.get(), |img| {
+            dither_dyn(opts, img, &quantize, threads)
+        }),
+        (None, None) => vec![dither_dyn(opts, img, &quantize, threads)],
+    }
+}
+
+/// dither `img` with the `--dither` algorithm, reporting progress to stderr with `--verbose`.
+fn dither_dyn<P>(
+    opts: &Opt,
+    img: Img<P>,
+    quantize: &dyn CloneQuantizer<P>,
+    threads: usize,
+) -> Img<P>
+where
+    dyn Algorithm: DynDither<P>,
+{
+    let progress = |done: usize, total: usize| {
+        if opts.verbose {
+            eprint!("\rdithered {}/{} rows", done, total);
+            if done == total {
+                eprintln!();
+            }
+        }
+    };
+    // the CLI never cancels, so this can't fail.
+    match opts
+        .ditherer
+        .dither_with_progress_dyn(img, quantize, &progress, &Cancel::new(), threads)
+    {
+        Ok(img) => img,
+        Err(err) => unreachable!("{}", err),
+    }
+}
+
+/// dither in floating point, using `S` as the working representation. returns one image per frame.
+fn dither_float<S: Working>(
+    opts: &Opt,
+    img: Img<RGB<S>>,
+    threads: usize,
+    sequence: Option<&mut Sequences<S>>,
+) -> Result<Vec<Img<RGB<u8>>>>
+where
+    dyn Algorithm: DynDither<S> + DynDither<RGB<S>> + DynDither<[S; 3]>,
+{
+    let depth = BitDepth::new(opts.bit_depth)?;
+    let space = opts.space.unwrap_or_default();
+    let distance = opts.distance.unwrap_or(space.distance());
+    let to_u8 =
+        |img: Img<RGB<S>>| img.convert_with(|rgb| rgb.convert_with(|x| clamp_f64_to_u8(x.into())));
+    let (gray, rgb, in_space, nearest) = match sequence {
+        Some(Sequences {
+            gray,
+            rgb,
+            space,
+            nearest,
+        }) => (Some(gray), Some(rgb), Some(space), Some(nearest)),
+        None => (None, None, None, None),
+    };
+
+    let edges = detect_edges(opts, &img);
+    let output_imgs = match &opts.color_mode {
+        color::Mode::Palette { .. } if opts.bit_depth > 1 => {
+            return Err(Error::CustomPaletteIncompatibleWithDepth);
+        }
+        color::Mode::Color | color::Mode::BlackAndWhite | color::Mode::SingleColor(_)
+            if opts.flat_tolerance.is_some() =>
+        {
+            return Err(Error::SmartRequiresPalette);
+        }
+
+        color::Mode::Color => {
+            let img = match opts.subpixel {
+                Some(stripe) => subpixel::split_by_channel(&img, stripe),
+                None => img,
+            };
+            dither_with(opts, img, PerChannel(depth), rgb, threads)
+                .into_iter()
+                .map(to_u8)
+                .collect()
+        }
+
+        color::Mode::Palette { palette: p, .. } => {
+            let search = if opts.lut { Search::Lut } else { Search::Auto };
+            let build = || palette::quantize_with(p, distance).search(search);
+            let nearest = match nearest {
+                Some(nearest) => nearest.get_or_insert_with(build).clone(),
+                None => build(),
+            };
+            match space {
+                Space::Srgb => {
+                    let quantize = palette_quantizer::<S, S>(opts, &img, &nearest);
+                    dither_with(opts, img, quantize, rgb, threads)
+                        .into_iter()
+                        .map(to_u8)
+                        .collect()
+                }
+                space => {
+                    let quantize = InSpace::new(space, palette_quantizer(opts, &img, &nearest));
+                    dither_with(opts, space.convert(img), quantize, in_space, threads)
+                        .into_iter()
+                        .map(|frame| to_u8(space.convert_back(frame)))
+                        .collect()
+                }
+            }
+        }
+
+        color::Mode::BlackAndWhite => {
+            let to_bw = |rgb: RGB<S>| {
+                S::from_f64(
+                    rgb.convert_with(Into::into)
+                        .to_chroma_corrected_black_and_white(),
+                )
+            };
+            dither_with(opts, img.convert_with(to_bw), depth, gray, threads)
+                .into_iter()
+                .map(|frame| {
+                    frame.convert_with(|x| RGB::from_chroma_corrected_black_and_white(x.into()))
+                })
+                .collect()
+        }
+
+        color::Mode::SingleColor(color) => {
+            if opts.verbose {
+                eprintln!("single_color mode: {:x}", color)
+            }
+            dither_with(opts, img, SingleColor::new(*color, depth), rgb, threads)
+                .into_iter()
+                .map(to_u8)
+                .collect()
+        }
+        color::Mode::Auto { .. } => unreachable!("run extracts auto palettes before dithering"),
+    };
+    Ok(outline(opts, edges.as_ref(), output_imgs))
+}
+
+/// the quantizer for a palette `--color`, working in `N`: `nearest`, or [Nearest::flat][palette::Nearest::flat]
+/// for `--smart`.
+fn palette_quantizer<S: Working, N: Float + 'static>(
+    opts: &Opt,
+    img: &Img<RGB<S>>,
+    nearest: &palette::Nearest,
+) -> Box<dyn CloneQuantizer<RGB<N>>> {
+    match opts.flat_tolerance {
+        Some(tolerance) => Box::new(nearest.flat(img, tolerance)),
+        None => Box::new(nearest.clone()),
+    }
+}
+
+/// dither each image in the `--sequence` directory in name order, saving them under the same names in `output`.
+fn dither_sequence<S: Working>(
+    opts: &Opt,
+    output: &Path,
+    tolerance: f64,
+    threads: usize,
+) -> Result<()>
+where
+    dyn Algorithm: DynDither<S> + DynDither<RGB<S>> + DynDither<[S; 3]>,
+{
+    let paths = sequence_paths(opts)?;
+    std::fs::create_dir_all(output).map_err(|err| Error::output(err, output))?;
+
+    let mut sequences = Sequences {
+        gray: Sequence::new(tolerance),
+        rgb: Sequence::new(tolerance),
+        space: Sequence::new(tolerance),
+        nearest: None,
+    };
+    for path in paths {
+        let img = load(opts, &path)?;
+        let output_imgs = dither_float(opts, img, threads, Some(&mut sequences))?;
+        finish(opts, &output.join(path.file_name().unwrap()), output_imgs)?;
+    }
+    Ok(())
+}
+
+/// the images in the `--sequence` directory, in name order.
+fn sequence_paths(opts: &Opt) -> Result<Vec<PathBuf>> {
+    let mut paths = std::fs::read_dir(&opts.input)
+        .and_then(|entries| {
+            entries
+                .map(|entry| Ok(entry?.path()))
+                .collect::<io::Result<Vec<_>>>()
+        })
+        .map_err(|err| Error::input(err, &opts.input))?;
+    paths.retain(|path| path.is_file() && image::ImageFormat::from_path(path).is_ok());
+    paths.sort();
+    Ok(paths)
+}
+
+/// the image to fit a palette to: the input image, or the first frame of the `--sequence`, so every frame shares
+/// the palette. `None` for an empty sequence.
+fn palette_source(opts: &Opt) -> Result<Option<Img<RGB<u8>>>> {
+    let path = match opts.sequence {
+        Some(_) => match sequence_paths(opts)?.into_iter().next() {
+            Some(path) => path,
+            None => return Ok(None),
+        },
+        None => opts.input.clone(),
+    };
+    Ok(Some(Img::load(&path)?))
+}
+
+/// replace `--color=auto:N` with a palette of `colors` colors extracted from the [palette_source].
+/// See [palette::extract].
+fn with_extracted_palette(opts: &Opt, colors: usize) -> Result<Opt> {
+    let Some(img) = palette_source(opts)? else {
+        return Ok(opts.clone());
+    };
+    let method = opts.extract.unwrap_or_default();
+    let palette = palette::extract::extract(&img, colors, method);
+    if opts.verbose {
+        let hex: Vec<String> = palette.iter().map(|c| format!("{:x}", c)).collect();
+        eprintln!(
+            "extracted a {}-color palette with {:?}: {}",
+            palette.len(),
+            method,
+            hex.join(" ")
+        );
+    }
+    Ok(Opt {
+        color_mode: color::Mode::Palette {
+            palette: palette.into(),
+            name: opts.color_mode.to_string().into(),
+        },
+        ..opts.clone()
+    })
+}
+
+/// replace a palette `--color` with its best `colors` colors for the [palette_source]. See [palette::subset].
+fn with_palette_subset(opts: &Opt, palette: &Palette, name: &str, colors: usize) -> Result<Opt> {
+    // like `--color=auto:1`, a single color can't be dithered.
+    if colors < 2 {
+        return Err(color::Error::PaletteTooSmall.into());
+    }
+    let Some(img) = palette_source(opts)? else {
+        return Ok(opts.clone());
+    };
+    let distance = opts
+        .distance
+        .unwrap_or(opts.space.unwrap_or_default().distance());
+    let subset = palette::subset::select(&img, palette, colors, distance);
+    if opts.verbose {
+        let hex: Vec<String> = subset.iter().map(|c| format!("{:x}", c)).collect();
+        eprintln!(
+            "picked {} of {} colors: {}",
+            subset.len(),
+            palette.len(),
+            hex.join(" ")
+        );
+    }
+    Ok(Opt {
+        color_mode: color::Mode::Palette {
+            palette: subset.into(),
+            name: format!("{}_best_{}", name, colors).into(),
+        },
+        ..opts.clone()
+    })
+}
+
+/// save the frames: as a single image, as numbered images, or as a packed stream.
+fn finish(opts: &Opt, output: &Path, output_imgs: Vec<Img<RGB<u8>>>) -> Result<()> {
+    if opts.verbose {
+        eprintln!("dithering complete.\nsaving...");
+    }
+    if opts.packed {
+        let mut packed = Vec::new();
+        for img in output_imgs {
+            let width = img.width() as usize;
+            let (row_len, levels): (usize, Vec<u8>) = match opts.color_mode {
+                color::Mode::Color => (3 * width, img.raw_buf()),
+                _ => (width, img.into_iter().map(|RGB(x, _, _)| x).collect()),
+            };
+            let levels: Vec<u8> = levels
+                .into_iter()
+                .map(|x| temporal::level(x, opts.bit_depth))
+                .collect();
+            packed.extend(temporal::pack(&levels, row_len, opts.bit_depth));
+        }
+        std::fs::write(output, packed).map_err(|err| Error::output(err, output))?;
+    } else if output_imgs.len() == 1 {
+        output_imgs.into_iter().next().unwrap().save(output)?;
+    } else {
+        let digits = (output_imgs.len() - 1).to_string().len();
+        let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = output.extension().unwrap_or_default().to_string_lossy();
+        for (i, img) in output_imgs.into_iter().enumerate() {
+            img.save(&output.with_file_name(format!("{stem}_{i:0digits$}.{extension}")))?;
+        }
+    }
+    if opts.verbose {
+        eprintln!("program finished");
+    }
+    Ok(())
+}
+
+/// dither with the fixed-point integer pipeline. See [fixed].
+fn dither_fixed(opts: &Opt, img: Img<RGB<u8>>) -> Result<Img<RGB<u8>>> {
+    let kernel = opts
+        .ditherer
+        .kernel()
+        .and_then(|kernel| kernel.to_fixed())
+        .ok_or_else(|| Error::FixedRequiresKernel(opts.ditherer.to_string()))?;
+    let quantize = fixed::quantize_n_bits(opts.bit_depth)?;
+    let width = img.width();
+    let edges = detect_edges(opts, &img);
+
+    let (channels, mut buf) = match &opts.color_mode {
+        color::Mode::Palette { .. } | color::Mode::Auto { .. } => {
+            return Err(Error::FixedIncompatibleWithPalette)
+        }
+        color::Mode::Color => match opts.subpixel {
+            Some(stripe) => {
+                let samples =
+                    subpixel::split_by_channel(&img.convert_with(RGB::<f64>::from), stripe);
+                (
+                    3,
+                    samples
+                        .convert_with(|rgb| rgb.convert_with(clamp_f64_to_u8))
+                        .raw_buf(),
+                )
+            }
+            None => (3, img.raw_buf()),
+        },
+        color::Mode::BlackAndWhite | color::Mode::SingleColor(_) => {
+            (1, img.into_iter().map(fixed::luma).collect())
+        }
+    };
+    let mut scratch = vec![0; kernel.scratch_len(width as usize, channels)];
+    kernel.dither(&mut buf, width as usize, channels, &mut scratch, quantize);
+
+    let output_img = match &opts.color_mode {
+        color::Mode::Color => Img::new(buf.chunks(3).map(|c| RGB(c[0], c[1], c[2])), width),
+        color::Mode::SingleColor(color) => {
+            let scale = |c: u8, x: u8| ((u16::from(c) * u16::from(x) + 127) / 255) as u8;
+            Img::new(
+                buf.into_iter().map(|x| color.convert_with(|c| scale(c, x))),
+                width,
+            )
+        }
+        _ => Img::new(buf.into_iter().map(|x| RGB(x, x, x)), width),
+    };
+    let output_imgs = outline(opts, edges.as_ref(), vec![output_img.unwrap()]);
+    Ok(output_imgs.into_iter().next().unwrap())
+}
+
+/// find the `--edges` of the input, to draw over the output. See [edges].
+fn detect_edges<N: Clone + Into<f64>>(opts: &Opt, img: &Img<RGB<N>>) -> Option<Img<bool>> {
+    let threshold = opts.edges?;
+    Some(edges::detect(
+        &edges::luma(img),
+        opts.edge_detector,
+        threshold,
+        opts.edge_thickness,
+    ))
+}
+
+/// draw the `edges`, if any, over each output frame in the `--edge-color`.
+fn outline(
+    opts: &Opt,
+    edges: Option<&Img<bool>>,
+    mut output_imgs: Vec<Img<RGB<u8>>>,
+) -> Vec<Img<RGB<u8>>> {
+    if let Some(edges) = edges {
+        for img in &mut output_imgs {
+            edges::overlay(img, edges, opts.edge_color);
+        }
+    }
+    output_imgs
+}
@@ -0,0 +1,48 @@
+use super::RGB;
+
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// CMYK represents the four ink separations (cyan, magenta, yellow, key) of a pixel,
+/// each from 0 (no ink) to 255 (full coverage).
+pub struct CMYK<N>(pub N, pub N, pub N, pub N);
+
+impl<P> CMYK<P> {
+    /// map a function across all channels of the CMYK.
+    /// ```
+    /// # use dither::color::CMYK;
+    /// assert_eq!(CMYK(2_u8, 5, 8, 0).convert_with(f64::from), CMYK(2., 5., 8., 0.));
+    /// ```
+    pub fn convert_with<Q>(self, mut convert: impl FnMut(P) -> Q) -> CMYK<Q> {
+        let CMYK(c, m, y, k) = self;
+        CMYK(convert(c), convert(m), convert(y), convert(k))
+    }
+}
+
+impl CMYK<f64> {
+    /// naive separation with full gray-component replacement: black comes entirely from the key channel.
+    /// ```
+    /// # use dither::prelude::*;
+    /// # use dither::color::CMYK;
+    /// assert_eq!(CMYK::from_rgb(RGB(255., 0., 51.)), CMYK(0., 255., 204., 0.));
+    /// assert_eq!(CMYK::from_rgb(RGB(0., 0., 0.)), CMYK(0., 0., 0., 255.));
+    /// ```
+    pub fn from_rgb(RGB(r, g, b): RGB<f64>) -> Self {
+        let k = 255. - f64::max(r, f64::max(g, b));
+        if k >= 255. {
+            return CMYK(0., 0., 0., 255.);
+        }
+        let ink = |c: f64| (255. - c - k) / (255. - k) * 255.;
+        CMYK(ink(r), ink(g), ink(b), k)
+    }
+
+    /// the color the inks print as; the inverse of [CMYK::from_rgb].
+    /// ```
+    /// # use dither::prelude::*;
+    /// # use dither::color::CMYK;
+    /// assert_eq!(CMYK(0., 255., 204., 0.).to_rgb(), RGB(255., 0., 51.));
+    /// ```
+    pub fn to_rgb(self) -> RGB<f64> {
+        let CMYK(c, m, y, k) = self;
+        let light = |ink: f64| (255. - ink) * (255. - k) / 255.;
+        RGB(light(c), light(m), light(y))
+    }
+}
@@ -0,0 +1,146 @@
+//! How far apart two colors look, for matching pixels to a palette. Corresponds to [Opt][crate::Opt] `--distance`.
+//!
+//! Raw sRGB distances are cheap, but treat every channel and every brightness alike, so they often pick colors
+//! that look wrong next to each other; the perceptual metrics are slower and closer to what the eye sees.
+//! ```
+//! # use dither::prelude::*;
+//! # use dither::color::{distance::ColorDistance, palette};
+//! # use dither::quantize::Quantizer;
+//! let palette = [RGB(0, 0, 0), RGB(255, 255, 255), RGB(0, 0, 255), RGB(128, 128, 128)];
+//! let navy = RGB(40., 40., 120.);
+//! let mut manhattan = palette::quantize(&palette);
+//! let mut perceptual = palette::quantize_with(&palette, ColorDistance::DeltaE2000);
+//! // navy is closer to gray in raw sRGB, but looks blue.
+//! assert_eq!(manhattan.nearest(&navy, (0, 0)), RGB(128., 128., 128.));
+//! assert_eq!(perceptual.nearest(&navy, (0, 0)), RGB(0., 0., 255.));
+//! ```
+use super::RGB;
+
+/// A metric for the distance between two colors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum ColorDistance {
+    /// the sum of the absolute differences of the sRGB channels. fast, and what the crate has always used.
+    #[default]
+    #[value(name = "manhattan")]
+    Manhattan,
+    /// the straight-line distance between sRGB colors.
+    #[value(name = "euclidean")]
+    Euclidean,
+    /// euclidean sRGB, with the channels weighted by how red the colors are:
+    /// a cheap approximation of perception. See <https://www.compuphase.com/cmetric.htm>.
+    #[value(name = "redmean")]
+    Redmean,
+    /// CIE 1976 ΔE: the straight-line distance in [CIELAB][RGB::to_lab].
+    #[value(name = "de76")]
+    DeltaE76,
+    /// CIEDE2000 ΔE: CIELAB corrected for how hue, chroma and lightness differences are seen. the most
+    /// accurate, and the slowest.
+    #[value(name = "de2000")]
+    DeltaE2000,
+    /// the straight-line distance in [Oklab][RGB::to_oklab]: nearly as accurate as ΔE2000, for the cost of ΔE76.
+    #[value(name = "oklab")]
+    Oklab,
+}
+
+impl ColorDistance {
+    /// the coordinates this metric compares a color in: sRGB, CIELAB or Oklab.
+    /// convert a palette once with this, then compare with [ColorDistance::between].
+    pub fn coordinates(self, rgb: &RGB<f64>) -> [f64; 3] {
+        match self {
+            ColorDistance::Manhattan | ColorDistance::Euclidean | ColorDistance::Redmean => {
+                let RGB(r, g, b) = rgb;
+                [*r, *g, *b]
+            }
+            ColorDistance::DeltaE76 | ColorDistance::DeltaE2000 => rgb.to_lab(),
+            ColorDistance::Oklab => rgb.to_oklab(),
+        }
+    }
+
+    /// the distance between two colors' [coordinates][ColorDistance::coordinates].
+    pub fn between(self, a: &[f64; 3], b: &[f64; 3]) -> f64 {
+        let [d0, d1, d2] = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+        match self {
+            ColorDistance::Manhattan => d0.abs() + d1.abs() + d2.abs(),
+            ColorDistance::Euclidean | ColorDistance::DeltaE76 | ColorDistance::Oklab => {
+                f64::sqrt(d0 * d0 + d1 * d1 + d2 * d2)
+            }
+            ColorDistance::Redmean => {
+                let red = (a[0] + b[0]) / 2.;
+                f64::sqrt(
+                    (2. + red / 256.) * d0 * d0
+                        + 4. * d1 * d1
+                        + (2. + (255. - red) / 256.) * d2 * d2,
+                )
+            }
+            ColorDistance::DeltaE2000 => delta_e_2000(a, b),
+        }
+    }
+
+    /// the distance between two colors.
+    /// ```
+    /// # use dither::prelude::*;
+    /// # use dither::color::distance::ColorDistance;
+    /// let (black, white) = (RGB(0., 0., 0.), RGB(255., 255., 255.));
+    /// assert_eq!(ColorDistance::Manhattan.distance(&black, &white), 765.);
+    /// assert!((ColorDistance::DeltaE76.distance(&black, &white) - 100.).abs() < 1e-3);
+    /// ```
+    pub fn distance(self, a: &RGB<f64>, b: &RGB<f64>) -> f64 {
+        self.between(&self.coordinates(a), &self.coordinates(b))
+    }
+}
+
+/// CIEDE2000 between two CIELAB colors, with unit weights.
+/// See Sharma, Wu & Dalal, "The CIEDE2000 Color-Difference Formula" (2005), whose test data this matches:
+/// ```
+/// # use dither::color::distance::ColorDistance;
+/// let de = |a, b| ColorDistance::DeltaE2000.between(&a, &b);
+/// assert!((de([50., 2.6772, -79.7751], [50., 0., -82.7485]) - 2.0425).abs() < 1e-4);
+/// assert!((de([50., 0., 0.], [50., -1., 2.]) - 2.3669).abs() < 1e-4);
+/// assert!((de([2.0776, 0.0795, -1.135], [0.9033, -0.0636, -0.5514]) - 0.9082).abs() < 1e-4);
+/// ```
+fn delta_e_2000(&[l1, a1, b1]: &[f64; 3], &[l2, a2, b2]: &[f64; 3]) -> f64 {
+    const POW_25_7: f64 = 6_103_515_625.;
+    let chroma_bar = (f64::hypot(a1, b1) + f64::hypot(a2, b2)) / 2.;
+    let g = 0.5 * (1. - f64::sqrt(chroma_bar.powi(7) / (chroma_bar.powi(7) + POW_25_7)));
+    let (a1, a2) = ((1. + g) * a1, (1. + g) * a2);
+    let (c1, c2) = (f64::hypot(a1, b1), f64::hypot(a2, b2));
+    let hue = |a: f64, b: f64| {
+        if a == 0. && b == 0. {
+            0.
+        } else {
+            f64::atan2(b, a).to_degrees().rem_euclid(360.)
+        }
+    };
+    let (h1, h2) = (hue(a1, b1), hue(a2, b2));
+
+    let delta_l = l2 - l1;
+    let delta_c = c2 - c1;
+    let delta_h = match h2 - h1 {
+        _ if c1 * c2 == 0. => 0.,
+        d if d > 180. => d - 360.,
+        d if d < -180. => d + 360.,
+        d => d,
+    };
+    let delta_h = 2. * f64::sqrt(c1 * c2) * f64::sin(delta_h.to_radians() / 2.);
+
+    let l_bar = (l1 + l2) / 2.;
+    let c_bar = (c1 + c2) / 2.;
+    let h_bar = match h1 + h2 {
+        sum if c1 * c2 == 0. => sum,
+        sum if (h1 - h2).abs() <= 180. => sum / 2.,
+        sum if sum < 360. => (sum + 360.) / 2.,
+        sum => (sum - 360.) / 2.,
+    };
+    let cos = |degrees: f64| f64::cos(degrees.to_radians());
+    let t = 1. - 0.17 * cos(h_bar - 30.) + 0.24 * cos(2. * h_bar) + 0.32 * cos(3. * h_bar + 6.)
+        - 0.20 * cos(4. * h_bar - 63.);
+    let delta_theta = 30. * f64::exp(-((h_bar - 275.) / 25.).powi(2));
+    let r_c = 2. * f64::sqrt(c_bar.powi(7) / (c_bar.powi(7) + POW_25_7));
+    let s_l = 1. + 0.015 * (l_bar - 50.).powi(2) / f64::sqrt(20. + (l_bar - 50.).powi(2));
+    let s_c = 1. + 0.045 * c_bar;
+    let s_h = 1. + 0.015 * c_bar * t;
+    let r_t = -f64::sin((2. * delta_theta).to_radians()) * r_c;
+
+    let (l, c, h) = (delta_l / s_l, delta_c / s_c, delta_h / s_h);
+    f64::sqrt(l * l + c * c + h * h + r_t * c * h)
+}
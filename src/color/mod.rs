@@ -1,16 +1,26 @@
 //! handling of color modes & [RGB].
 
+mod cmyk;
 mod rgb;
+mod rgba;
 
+pub use cmyk::CMYK;
 pub use palette::Palette;
-use palette::{cga, crayon};
+use palette::{
+    amstrad_cpc, apple2, c64, cga, crayon, ega, epaper, gameboy, mac16, msx, nes, pico8, teletext,
+    vga, web_safe, windows16, zx_spectrum,
+};
 pub use rgb::RGB;
+pub use rgba::RGBA;
 
+pub mod distance;
 pub mod palette;
+pub mod space;
+pub mod subpixel;
 use std::borrow::Cow;
 use std::path::Path;
 use std::str::FromStr;
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
 /// Mode is the color mode the program runs in. Corresponds to [Opt][crate::Opt] `--color`
 pub enum Mode {
     /// A single known [RGB] color.
@@ -22,6 +32,7 @@ pub enum Mode {
     Color,
     /// Grayscale dithering to the user-specified bit depth.
     /// - `-color="bw"`(default)
+    #[default]
     BlackAndWhite,
     /// A user-specified palette, read from a file or the CGA option. See [parse-palette] and the readme for details on palette files.
     /// - `color==$FILENAME`
@@ -30,6 +41,10 @@ pub enum Mode {
         palette: Cow<'static, Palette>,
         name: Cow<'static, str>,
     },
+    /// A palette of up to `colors` colors, [extracted][palette::extract] from the input image. the CLI replaces it
+    /// with a [Mode::Palette] once the image is loaded.
+    /// - `--color=auto:16`
+    Auto { colors: usize },
 }
 
 impl From<String> for Mode {
@@ -50,10 +65,12 @@ impl Mode {
         palette: Cow::Borrowed(crayon::ALL),
         name: Cow::Borrowed("CRAYON"),
     };
-}
-impl Default for Mode {
-    fn default() -> Self {
-        Mode::BlackAndWhite
+    /// one of the other built-in palettes in [palette].
+    const fn built_in(palette: &'static Palette, name: &'static str) -> Self {
+        Mode::Palette {
+            palette: Cow::Borrowed(palette),
+            name: Cow::Borrowed(name),
+        }
     }
 }
 
@@ -69,12 +86,18 @@ pub enum Error {
 
     /// An error accessing a file
     BadFile { path: String, err: std::io::Error },
+    /// A palette file that isn't valid in its [format][palette::format::Format]
+    BadPalette {
+        format: palette::format::Format,
+        reason: String,
+    },
 }
 
 impl std::fmt::Display for Mode {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Mode::Palette { name, .. } => write!(f, "custom_palette_{}", name),
+            Mode::Auto { colors } => write!(f, "auto_{}", colors),
             Mode::Color => write!(f, "color"),
             Mode::SingleColor(color) => write!(f, "single_color_{:x}", color),
             Mode::BlackAndWhite => write!(f, "bw"),
@@ -82,7 +105,7 @@ impl std::fmt::Display for Mode {
     }
 }
 
-impl<'a> FromStr for Mode {
+impl FromStr for Mode {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s.trim().to_ascii_uppercase().as_ref() {
@@ -90,6 +113,23 @@ impl<'a> FromStr for Mode {
             "C" | "COLOR" => Mode::Color,
             "CGA" => Mode::CGA,
             "CRAYON" => Mode::CRAYON,
+            "EGA" => Mode::built_in(ega::ALL, "EGA"),
+            "VGA" => Mode::built_in(vga::ALL, "VGA"),
+            "GAMEBOY" | "GB" | "DMG" => Mode::built_in(gameboy::ALL, "GAMEBOY"),
+            "PICO8" | "PICO-8" => Mode::built_in(pico8::ALL, "PICO8"),
+            "NES" => Mode::built_in(nes::ALL, "NES"),
+            "C64" | "PEPTO" => Mode::built_in(c64::ALL, "C64"),
+            "ZX" | "SPECTRUM" | "ZX_SPECTRUM" => Mode::built_in(zx_spectrum::ALL, "ZX_SPECTRUM"),
+            "APPLE2" | "APPLE_II" => Mode::built_in(apple2::ALL, "APPLE2"),
+            "MSX" => Mode::built_in(msx::ALL, "MSX"),
+            "CPC" | "AMSTRAD_CPC" => Mode::built_in(amstrad_cpc::ALL, "AMSTRAD_CPC"),
+            "MAC16" | "MACINTOSH" => Mode::built_in(mac16::ALL, "MAC16"),
+            "WEBSAFE" | "WEB_SAFE" => Mode::built_in(web_safe::ALL, "WEB_SAFE"),
+            "WIN16" | "WINDOWS" => Mode::built_in(windows16::ALL, "WIN16"),
+            "TELETEXT" => Mode::built_in(teletext::ALL, "TELETEXT"),
+            "EPAPER3" | "EPAPER3_RED" => Mode::built_in(epaper::THREE_COLOR_RED, "EPAPER3_RED"),
+            "EPAPER3_YELLOW" => Mode::built_in(epaper::THREE_COLOR_YELLOW, "EPAPER3_YELLOW"),
+            "EPAPER7" => Mode::built_in(epaper::SEVEN_COLOR, "EPAPER7"),
 
             "BLUE" => Mode::SingleColor(cga::BLUE),
             "GREEN" => Mode::SingleColor(cga::GREEN),
@@ -106,18 +146,21 @@ impl<'a> FromStr for Mode {
             "LIGHT_MAGENTA" => Mode::SingleColor(cga::LIGHT_MAGENTA),
             "YELLOW" => Mode::SingleColor(cga::YELLOW),
 
-            _ if Path::new(s).is_file() => {
-                let path = s.to_string();
-                return match std::fs::read_to_string(s) {
-                    // note: we don't use
-                    Ok(contents) => Ok(Mode::Palette {
-                        palette: palette::parse(&contents)?,
-                        name: Cow::Owned(path),
-                    }),
-                    Err(err) => Err(Error::BadFile { err, path }),
-                };
+            auto if auto.starts_with("AUTO:") => {
+                let colors: usize = auto["AUTO:".len()..]
+                    .parse()
+                    .map_err(|_| Error::UnknownOption(s.to_string()))?;
+                if colors < 2 {
+                    return Err(Error::PaletteTooSmall);
+                }
+                Mode::Auto { colors }
             }
 
+            _ if Path::new(s).is_file() => Mode::Palette {
+                palette: Cow::Owned(palette::format::load(Path::new(s))?),
+                name: Cow::Owned(s.to_string()),
+            },
+
             _ => return Err(Error::UnknownOption(s.to_string())),
         })
     }
@@ -132,17 +175,30 @@ impl std::fmt::Display for Error {
                 "user-specified palette has 0 or 1 color; must have at least two"
             ),
             Error::RGBParse =>        write!(f, "could not parse to a RGB value: bad format. must be exactly six hexidecimal characters, with optional 0x prefix"),
+            Error::BadPalette { format, reason } => write!(f, "could not read {} palette: {}", format, reason),
             Error::BadFile{path, err} => write!(f, "could not load color palette from file at path \"{}\": {}", path, err),
         }
     }
 }
 
+impl std::error::Error for Error {}
+
 impl PartialEq for Error {
     fn eq(&self, other: &Self) -> bool {
         use self::Error::*;
         match (self, other) {
             (RGBParse, RGBParse) | (PaletteTooSmall, PaletteTooSmall) => true,
             (UnknownOption(a), UnknownOption(b)) => a == b,
+            (
+                BadPalette {
+                    format: f0,
+                    reason: r0,
+                },
+                BadPalette {
+                    format: f1,
+                    reason: r1,
+                },
+            ) => f0 == f1 && r0 == r1,
             (BadFile { path: p0, err: e0 }, BadFile { path: p1, err: e1 }) => {
                 p0 == p1 && e0.to_string() == e1.to_string()
             }
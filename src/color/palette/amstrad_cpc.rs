@@ -0,0 +1,15 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// the 27 colors of the Amstrad CPC, in firmware color number order: every combination of off, half and full red,
+/// green and blue, where the number is `9 * green + 3 * red + blue`.
+pub const ALL: &Palette = &{
+    const LEVELS: [u8; 3] = [0x00, 0x80, 0xFF];
+    let mut all = [RGB(0, 0, 0); 27];
+    let mut i = 0;
+    while i < 27 {
+        all[i] = RGB(LEVELS[i / 3 % 3], LEVELS[i / 9], LEVELS[i % 3]);
+        i += 1;
+    }
+    all
+};
@@ -0,0 +1,37 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// the 15 distinct low-resolution colors of the Apple II, in its color number order; numbers 5 and 10 are both gray.
+pub const ALL: &Palette = &[
+    BLACK,
+    MAGENTA,
+    DARK_BLUE,
+    PURPLE,
+    DARK_GREEN,
+    GRAY,
+    MEDIUM_BLUE,
+    LIGHT_BLUE,
+    BROWN,
+    ORANGE,
+    PINK,
+    LIGHT_GREEN,
+    YELLOW,
+    AQUAMARINE,
+    WHITE,
+];
+
+pub const BLACK: RGB<u8> = RGB(0x00, 0x00, 0x00);
+pub const MAGENTA: RGB<u8> = RGB(0x90, 0x17, 0x40);
+pub const DARK_BLUE: RGB<u8> = RGB(0x40, 0x2C, 0xA5);
+pub const PURPLE: RGB<u8> = RGB(0xD0, 0x43, 0xE5);
+pub const DARK_GREEN: RGB<u8> = RGB(0x00, 0x69, 0x40);
+pub const GRAY: RGB<u8> = RGB(0x80, 0x80, 0x80);
+pub const MEDIUM_BLUE: RGB<u8> = RGB(0x2F, 0x95, 0xE5);
+pub const LIGHT_BLUE: RGB<u8> = RGB(0xBF, 0xAB, 0xFF);
+pub const BROWN: RGB<u8> = RGB(0x40, 0x54, 0x00);
+pub const ORANGE: RGB<u8> = RGB(0xD0, 0x6A, 0x1A);
+pub const PINK: RGB<u8> = RGB(0xFF, 0x96, 0xBF);
+pub const LIGHT_GREEN: RGB<u8> = RGB(0x2F, 0xBC, 0x1A);
+pub const YELLOW: RGB<u8> = RGB(0xBF, 0xD3, 0x5A);
+pub const AQUAMARINE: RGB<u8> = RGB(0x6F, 0xE8, 0xBF);
+pub const WHITE: RGB<u8> = RGB(0xFF, 0xFF, 0xFF);
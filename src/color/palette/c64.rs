@@ -0,0 +1,39 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// the 16 colors of the Commodore 64, in its color number order, as measured by Philip "Pepto" Timmermann.
+pub const ALL: &Palette = &[
+    BLACK,
+    WHITE,
+    RED,
+    CYAN,
+    PURPLE,
+    GREEN,
+    BLUE,
+    YELLOW,
+    ORANGE,
+    BROWN,
+    LIGHT_RED,
+    DARK_GRAY,
+    GRAY,
+    LIGHT_GREEN,
+    LIGHT_BLUE,
+    LIGHT_GRAY,
+];
+
+pub const BLACK: RGB<u8> = RGB(0x00, 0x00, 0x00);
+pub const WHITE: RGB<u8> = RGB(0xFF, 0xFF, 0xFF);
+pub const RED: RGB<u8> = RGB(0x68, 0x37, 0x2B);
+pub const CYAN: RGB<u8> = RGB(0x70, 0xA4, 0xB2);
+pub const PURPLE: RGB<u8> = RGB(0x6F, 0x3D, 0x86);
+pub const GREEN: RGB<u8> = RGB(0x58, 0x8D, 0x43);
+pub const BLUE: RGB<u8> = RGB(0x35, 0x28, 0x79);
+pub const YELLOW: RGB<u8> = RGB(0xB8, 0xC7, 0x6F);
+pub const ORANGE: RGB<u8> = RGB(0x6F, 0x4F, 0x25);
+pub const BROWN: RGB<u8> = RGB(0x43, 0x39, 0x00);
+pub const LIGHT_RED: RGB<u8> = RGB(0x9A, 0x67, 0x59);
+pub const DARK_GRAY: RGB<u8> = RGB(0x44, 0x44, 0x44);
+pub const GRAY: RGB<u8> = RGB(0x6C, 0x6C, 0x6C);
+pub const LIGHT_GREEN: RGB<u8> = RGB(0x9A, 0xD2, 0x84);
+pub const LIGHT_BLUE: RGB<u8> = RGB(0x6C, 0x5E, 0xB5);
+pub const LIGHT_GRAY: RGB<u8> = RGB(0x95, 0x95, 0x95);
@@ -3,7 +3,6 @@ use crate::color::{rgb::RGB, Palette};
 /// All the colors defined by the constants in this crate;
 /// BLACK,BLUE,GREEN,CYAN,RED,MAGENTA,BROWN,LIGHT_GRAY,GRAY,LIGHT_BLUE,LIGHT_GREEN,LIGHT_CYAN,LIGHT_RED,LIGHT_MAGENTA,YELLOW,WHITE,
 /// TRUE_BLACK, TRUE_WHITE
-
 pub const ALL: &Palette = &[
     BLACK,
     BLUE,
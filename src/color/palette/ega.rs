@@ -0,0 +1,19 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// all 64 colors of the EGA, in hardware order: each color number is `rgbRGB` in binary, where the capital bits
+/// add 0xAA to their channel and the lowercase ones 0x55. the 16 default colors are [cga::ALL][super::cga::ALL].
+pub const ALL: &Palette = &{
+    let mut all = [RGB(0, 0, 0); 64];
+    let mut i = 0;
+    while i < 64 {
+        all[i] = RGB(channel(i, 2), channel(i, 1), channel(i, 0));
+        i += 1;
+    }
+    all
+};
+
+/// the level of the channel whose high bit is `bit` and low bit `bit + 3`.
+const fn channel(color: usize, bit: usize) -> u8 {
+    ((color >> bit & 1) * 0xAA + (color >> (bit + 3) & 1) * 0x55) as u8
+}
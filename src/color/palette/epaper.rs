@@ -0,0 +1,17 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// black, white and red: three-color e-paper panels.
+pub const THREE_COLOR_RED: &Palette = &[BLACK, WHITE, RED];
+/// black, white and yellow: three-color e-paper panels.
+pub const THREE_COLOR_YELLOW: &Palette = &[BLACK, WHITE, YELLOW];
+/// the seven inks of Advanced Color ePaper (ACeP) panels.
+pub const SEVEN_COLOR: &Palette = &[BLACK, WHITE, GREEN, BLUE, RED, YELLOW, ORANGE];
+
+pub const BLACK: RGB<u8> = RGB(0x00, 0x00, 0x00);
+pub const WHITE: RGB<u8> = RGB(0xFF, 0xFF, 0xFF);
+pub const GREEN: RGB<u8> = RGB(0x00, 0xFF, 0x00);
+pub const BLUE: RGB<u8> = RGB(0x00, 0x00, 0xFF);
+pub const RED: RGB<u8> = RGB(0xFF, 0x00, 0x00);
+pub const YELLOW: RGB<u8> = RGB(0xFF, 0xFF, 0x00);
+pub const ORANGE: RGB<u8> = RGB(0xFF, 0x80, 0x00);
@@ -0,0 +1,441 @@
+//! Palette extraction: compute an N-color palette from an image, to dither it to its own colors.
+//! Corresponds to [Opt][crate::Opt] `--color=auto:N` and `--extract`.
+//! ```
+//! # use dither::prelude::*;
+//! # use dither::color::palette::{self, extract::{self, Method}};
+//! // a red-to-blue gradient.
+//! let img: Img<RGB<u8>> = Img::new((0..256).map(|i| RGB(255 - i as u8, 0, i as u8)), 16).unwrap();
+//! let colors = extract::extract(&img, 4, Method::Wu);
+//! assert_eq!(colors.len(), 4);
+//! let img = img.convert_with(|rgb| rgb.convert_with(f64::from));
+//! let got = Ditherer::default().dither(img, palette::quantize(&colors));
+//! assert!(got.iter().all(|p| colors.contains(&p.clone().convert_with(dither::clamp_f64_to_u8))));
+//! ```
+use super::search::{Index, Search};
+use crate::color::distance::ColorDistance;
+use crate::color::RGB;
+use crate::Img;
+use std::collections::HashMap;
+
+/// An algorithm to extract a palette with. Corresponds to [Opt][crate::Opt] `--extract`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum Method {
+    /// Heckbert's median cut: split the box of colors with the widest channel at its median, until there are enough.
+    #[value(name = "median-cut")]
+    MedianCut,
+    /// k-means clustering, seeded with k-means++: the lowest error, and the slowest.
+    #[value(name = "kmeans")]
+    KMeans,
+    /// Gervautz and Purgathofer's octree: merge the least common colors that share the most leading bits.
+    #[value(name = "octree")]
+    Octree,
+    /// Wu's quantizer: split boxes of a 32x32x32 histogram to minimize the variance within them. fast, and close
+    /// to k-means. colors that share a cell of the histogram are never split, so it can return fewer colors.
+    #[default]
+    #[value(name = "wu")]
+    Wu,
+}
+
+/// compute a palette of up to `colors` colors from `img` with `method`. images with fewer distinct colors than
+/// that get all of them, except with [Method::Wu]. channels are rounded and clamped to `0..=255` first.
+/// ```
+/// # use dither::prelude::*;
+/// # use dither::color::palette::extract::{self, Method};
+/// let img: Img<RGB<u8>> = Img::new([RGB(0, 0, 0), RGB(255, 0, 0), RGB(255, 0, 0), RGB(0, 0, 0)], 2).unwrap();
+/// let mut colors = extract::extract(&img, 16, Method::KMeans);
+/// colors.sort_by_key(|c| c.to_hex());
+/// assert_eq!(colors, vec![RGB(0, 0, 0), RGB(255, 0, 0)]);
+/// ```
+pub fn extract<N: Into<f64> + Clone>(
+    img: &Img<RGB<N>>,
+    colors: usize,
+    method: Method,
+) -> Vec<RGB<u8>> {
+    let mut histogram = histogram(img);
+    if colors == 0 || histogram.is_empty() {
+        return Vec::new();
+    }
+    let palette = match method {
+        Method::MedianCut => median_cut(&mut histogram, colors),
+        Method::KMeans => k_means(&histogram, colors),
+        Method::Octree => octree(&histogram, colors),
+        Method::Wu => wu(&histogram, colors),
+    };
+    let mut extracted: Vec<RGB<u8>> = Vec::with_capacity(palette.len());
+    for c in palette {
+        let c = RGB::from(c.map(crate::clamp_f64_to_u8));
+        // nearby means can round to the same color.
+        if !extracted.contains(&c) {
+            extracted.push(c);
+        }
+    }
+    extracted
+}
+
+/// a color from the histogram, and how many pixels have it.
+pub(super) type Entry = ([u8; 3], u64);
+
+/// the distinct colors of `img` and their counts, in RGB order.
+pub(super) fn histogram<N: Into<f64> + Clone>(img: &Img<RGB<N>>) -> Vec<Entry> {
+    let mut counts: HashMap<[u8; 3], u64> = HashMap::new();
+    for rgb in img.iter() {
+        let RGB(r, g, b) = rgb
+            .clone()
+            .convert_with(|c| crate::clamp_f64_to_u8(c.into()));
+        *counts.entry([r, g, b]).or_default() += 1;
+    }
+    let mut histogram: Vec<Entry> = counts.into_iter().collect();
+    histogram.sort_unstable();
+    histogram
+}
+
+/// the mean color of `entries`, weighted by their counts.
+pub(super) fn mean(entries: &[Entry]) -> [f64; 3] {
+    let (mut sum, mut count) = ([0.; 3], 0.);
+    for &(c, n) in entries {
+        for (s, c) in sum.iter_mut().zip(c) {
+            *s += f64::from(c) * n as f64;
+        }
+        count += n as f64;
+    }
+    sum.map(|s| s / count)
+}
+
+fn median_cut(histogram: &mut [Entry], colors: usize) -> Vec<[f64; 3]> {
+    // the widest channel of a box, and how wide it is.
+    let widest = |entries: &[Entry]| {
+        (0..3)
+            .map(|axis| {
+                let values = entries.iter().map(|(c, _)| c[axis]);
+                let range = values.clone().max().unwrap() - values.min().unwrap();
+                (range, axis)
+            })
+            .max()
+            .unwrap()
+    };
+    // each box is a range of the histogram, `(start, end)`.
+    let mut boxes = vec![(0, histogram.len())];
+    while boxes.len() < colors {
+        let Some((i, (_, axis))) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, &(start, end))| end - start > 1)
+            .map(|(i, &(start, end))| (i, widest(&histogram[start..end])))
+            .max_by_key(|&(_, (range, _))| range)
+        else {
+            break;
+        };
+        let (start, end) = boxes[i];
+        let entries = &mut histogram[start..end];
+        entries.sort_unstable_by_key(|(c, _)| c[axis]);
+        // split after the median pixel, keeping a color on each side.
+        let half = entries.iter().map(|&(_, n)| n).sum::<u64>() / 2;
+        let mut seen = 0;
+        let median = entries
+            .iter()
+            .position(|&(_, n)| {
+                seen += n;
+                seen > half
+            })
+            .unwrap();
+        let split = start + (median + 1).clamp(1, entries.len() - 1);
+        boxes[i] = (start, split);
+        boxes.push((split, end));
+    }
+    boxes
+        .into_iter()
+        .map(|(start, end)| mean(&histogram[start..end]))
+        .collect()
+}
+
+fn k_means(histogram: &[Entry], colors: usize) -> Vec<[f64; 3]> {
+    const ROUNDS: usize = 32;
+    let point = |c: [u8; 3]| c.map(f64::from);
+    let squared = |a: [f64; 3], b: [f64; 3]| (0..3).map(|i| (a[i] - b[i]).powi(2)).sum::<f64>();
+    // a fixed seed, so the same image always gets the same palette.
+    let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut random = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed >> 11) as f64 / (1u64 << 53) as f64
+    };
+
+    // k-means++: start from the most common color, then pick each next center with a probability proportional
+    // to how many pixels it has, times its squared distance to the nearest center so far.
+    let first = histogram.iter().max_by_key(|&&(_, n)| n).unwrap().0;
+    let mut centers = vec![point(first)];
+    let mut nearest: Vec<f64> = histogram
+        .iter()
+        .map(|&(c, n)| n as f64 * squared(point(c), centers[0]))
+        .collect();
+    while centers.len() < colors {
+        let total: f64 = nearest.iter().sum();
+        if total <= 0. {
+            break;
+        }
+        let mut target = random() * total;
+        let i = nearest
+            .iter()
+            .position(|&d| {
+                target -= d;
+                target < 0.
+            })
+            .unwrap_or(nearest.len() - 1);
+        let center = point(histogram[i].0);
+        centers.push(center);
+        for (d, &(c, n)) in nearest.iter_mut().zip(histogram) {
+            *d = d.min(n as f64 * squared(point(c), center));
+        }
+    }
+
+    // Lloyd's algorithm, finding each color's nearest center with the palette search.
+    let mut assignments = vec![usize::MAX; histogram.len()];
+    for _ in 0..ROUNDS {
+        let index = Index::build(Search::Auto, &centers, ColorDistance::Euclidean);
+        let mut changed = false;
+        for (a, &(c, _)) in assignments.iter_mut().zip(histogram) {
+            let nearest = index.find_rgb(&centers, ColorDistance::Euclidean, &RGB::from(point(c)));
+            changed |= *a != nearest;
+            *a = nearest;
+        }
+        if !changed {
+            break;
+        }
+        let mut sums = vec![([0.; 3], 0.); centers.len()];
+        for (&a, &(c, n)) in assignments.iter().zip(histogram) {
+            let (sum, count) = &mut sums[a];
+            for (s, c) in sum.iter_mut().zip(point(c)) {
+                *s += c * n as f64;
+            }
+            *count += n as f64;
+        }
+        for (center, (sum, count)) in centers.iter_mut().zip(sums) {
+            // an empty cluster keeps its center.
+            if count > 0. {
+                *center = sum.map(|s| s / count);
+            }
+        }
+    }
+    centers
+}
+
+fn octree(histogram: &[Entry], colors: usize) -> Vec<[f64; 3]> {
+    /// a node of the tree, with the totals of every color under it. 0 is the root, so it marks a missing child.
+    #[derive(Default)]
+    struct Node {
+        children: [usize; 8],
+        sum: [f64; 3],
+        count: u64,
+    }
+    const DEPTH: usize = 8;
+    let mut nodes = vec![Node::default()];
+    // the nodes at each depth with children, to merge from the bottom up.
+    let mut parents: Vec<Vec<usize>> = vec![Vec::new(); DEPTH];
+    for &(c, n) in histogram {
+        let add = |node: &mut Node| {
+            for (s, c) in node.sum.iter_mut().zip(c) {
+                *s += f64::from(c) * n as f64;
+            }
+            node.count += n;
+        };
+        let mut node = 0;
+        add(&mut nodes[node]);
+        for (depth, level) in parents.iter_mut().enumerate() {
+            let bit = |c: u8| usize::from((c >> (7 - depth)) & 1);
+            let child = (bit(c[0]) << 2) | (bit(c[1]) << 1) | bit(c[2]);
+            if nodes[node].children[child] == 0 {
+                if nodes[node].children == [0; 8] {
+                    level.push(node);
+                }
+                nodes.push(Node::default());
+                nodes[node].children[child] = nodes.len() - 1;
+            }
+            node = nodes[node].children[child];
+            add(&mut nodes[node]);
+        }
+    }
+
+    // every color is a leaf; merge the children of the least common deepest nodes into them until few enough remain.
+    // by the time a depth is merged, every node below it is a leaf.
+    let mut leaves = histogram.len();
+    for depth in (0..DEPTH).rev() {
+        let level = &mut parents[depth];
+        level.sort_by_key(|&node| nodes[node].count);
+        for &node in level.iter() {
+            let excess = leaves - leaves.min(colors);
+            if excess == 0 {
+                break;
+            }
+            let mut children: Vec<usize> = nodes[node]
+                .children
+                .iter()
+                .copied()
+                .filter(|&c| c != 0)
+                .collect();
+            if children.len() - 1 <= excess {
+                leaves -= children.len() - 1;
+                nodes[node].children = [0; 8];
+                continue;
+            }
+            // merging them all would leave too few colors: merge just the least common ones into one leaf.
+            children.sort_by_key(|&c| nodes[c].count);
+            let (into, rest) = (children[0], &children[1..=excess]);
+            for &c in rest {
+                let (sum, count) = (nodes[c].sum, nodes[c].count);
+                for (s, c) in nodes[into].sum.iter_mut().zip(sum) {
+                    *s += c;
+                }
+                nodes[into].count += count;
+                for slot in nodes[node].children.iter_mut().filter(|slot| **slot == c) {
+                    *slot = 0;
+                }
+            }
+            leaves -= excess;
+        }
+    }
+
+    let mut palette = Vec::with_capacity(leaves);
+    let mut stack = vec![0];
+    while let Some(node) = stack.pop() {
+        let Node {
+            children,
+            sum,
+            count,
+        } = &nodes[node];
+        if *children == [0; 8] {
+            palette.push(sum.map(|s| s / *count as f64));
+        } else {
+            stack.extend(children.iter().rev().filter(|&&c| c != 0));
+        }
+    }
+    palette
+}
+
+fn wu(histogram: &[Entry], colors: usize) -> Vec<[f64; 3]> {
+    /// cells per channel: 32, plus a row of zeros so every box can be measured by inclusion-exclusion.
+    const SIDE: usize = 33;
+    /// a box of cells, `(lo, hi]` on each channel.
+    #[derive(Clone, Copy)]
+    struct Cube {
+        lo: [usize; 3],
+        hi: [usize; 3],
+    }
+    /// the cumulative moments of the histogram: pixel count, the sum of each channel, and the sum of squares.
+    struct Moments {
+        weight: Vec<f64>,
+        sums: [Vec<f64>; 3],
+        squares: Vec<f64>,
+    }
+    let at = |[r, g, b]: [usize; 3]| (r * SIDE + g) * SIDE + b;
+
+    let cells = SIDE * SIDE * SIDE;
+    let mut m = Moments {
+        weight: vec![0.; cells],
+        sums: std::array::from_fn(|_| vec![0.; cells]),
+        squares: vec![0.; cells],
+    };
+    for &(c, n) in histogram {
+        let i = at(c.map(|c| usize::from(c >> 3) + 1));
+        let (c, n) = (c.map(f64::from), n as f64);
+        m.weight[i] += n;
+        for (sum, c) in m.sums.iter_mut().zip(c) {
+            sum[i] += c * n;
+        }
+        m.squares[i] += n * (c[0] * c[0] + c[1] * c[1] + c[2] * c[2]);
+    }
+    // prefix sums along each axis make each cell the total of every cell at or below it.
+    for axis in 0..3 {
+        let step = [SIDE * SIDE, SIDE, 1][axis];
+        for moment in [&mut m.weight, &mut m.squares]
+            .into_iter()
+            .chain(m.sums.iter_mut())
+        {
+            for i in 0..cells {
+                if !(i / step).is_multiple_of(SIDE) {
+                    moment[i] += moment[i - step];
+                }
+            }
+        }
+    }
+    let volume = |cube: &Cube, moment: &[f64]| {
+        let (lo, hi) = (cube.lo, cube.hi);
+        let mut total = 0.;
+        for corner in 0..8u32 {
+            let pick = |axis: usize| (corner >> axis) & 1 == 1;
+            let point = std::array::from_fn(|axis| if pick(axis) { lo[axis] } else { hi[axis] });
+            let sign = if corner.count_ones() % 2 == 0 {
+                1.
+            } else {
+                -1.
+            };
+            total += sign * moment[at(point)];
+        }
+        total
+    };
+    // the total of (sum of channel)^2 / count over the box: higher is a tighter fit.
+    let fit = |cube: &Cube| {
+        let weight = volume(cube, &m.weight);
+        if weight <= 0. {
+            return None;
+        }
+        let squared: f64 = m.sums.iter().map(|sum| volume(cube, sum).powi(2)).sum();
+        Some(squared / weight)
+    };
+    let variance = |cube: &Cube| {
+        let cells: usize = (0..3).map(|axis| cube.hi[axis] - cube.lo[axis]).product();
+        match fit(cube) {
+            Some(fit) if cells > 1 => volume(cube, &m.squares) - fit,
+            _ => 0.,
+        }
+    };
+    // the split of `cube` that leaves the least variance in its halves, if any leaves pixels in both.
+    let cut = |cube: &Cube| {
+        let mut best: Option<(f64, Cube, Cube)> = None;
+        for axis in 0..3 {
+            for split in cube.lo[axis] + 1..cube.hi[axis] {
+                let (mut low, mut high) = (*cube, *cube);
+                low.hi[axis] = split;
+                high.lo[axis] = split;
+                if let (Some(a), Some(b)) = (fit(&low), fit(&high)) {
+                    if best.as_ref().is_none_or(|(score, ..)| a + b > *score) {
+                        best = Some((a + b, low, high));
+                    }
+                }
+            }
+        }
+        best.map(|(_, low, high)| (low, high))
+    };
+
+    let mut cubes = vec![Cube {
+        lo: [0; 3],
+        hi: [SIDE - 1; 3],
+    }];
+    let mut variances = vec![variance(&cubes[0])];
+    while cubes.len() < colors {
+        let (next, &v) = variances
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .unwrap();
+        if v <= 0. {
+            break;
+        }
+        match cut(&cubes[next]) {
+            Some((low, high)) => {
+                variances[next] = variance(&low);
+                variances.push(variance(&high));
+                cubes[next] = low;
+                cubes.push(high);
+            }
+            None => variances[next] = 0.,
+        }
+    }
+    cubes
+        .iter()
+        .map(|cube| {
+            let weight = volume(cube, &m.weight);
+            std::array::from_fn(|axis| volume(cube, &m.sums[axis]) / weight)
+        })
+        .collect()
+}
@@ -0,0 +1,437 @@
+//! Palette file formats, for `--color=$FILENAME` and `--save-palette`. Besides this crate's own `.plt`, palettes
+//! can be read from and written to GIMP `.gpl`, JASC-PAL `.pal`, Paint.NET `.txt`, Adobe `.aco` and `.ase`, and
+//! raw `.hex` lists.
+//! ```
+//! # use dither::prelude::*;
+//! # use dither::color::palette::format::{self, Format};
+//! let gpl = "GIMP Palette\nName: Sunset\n#\n255 128   0 orange\n 20  20  80\tnavy\n";
+//! assert_eq!(Format::detect(None, gpl.as_bytes()), Format::Gpl);
+//! assert_eq!(format::read(gpl.as_bytes(), Format::Gpl).unwrap(), vec![RGB(255, 128, 0), RGB(20, 20, 80)]);
+//! ```
+use super::super::{Error, CMYK, RGB};
+use super::Palette;
+use std::path::Path;
+
+/// A palette file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// this crate's format: one hexadecimal color per line, with optional `0x` prefix and `//` comments.
+    /// See [parse][super::parse].
+    Plt,
+    /// a GIMP palette: a `GIMP Palette` header, then decimal `R G B name` lines.
+    Gpl,
+    /// a JASC-PAL palette, from Paint Shop Pro: a `JASC-PAL` header, a version, a count, then decimal `R G B` lines.
+    JascPal,
+    /// a Paint.NET palette: `AARRGGBB` hexadecimal lines, with `;` comments. alpha is ignored.
+    PaintNet,
+    /// an Adobe Color Swatch file, from Photoshop: binary, with RGB, HSB, CMYK, Lab or grayscale colors.
+    Aco,
+    /// an Adobe Swatch Exchange file, from Illustrator and InDesign: binary, with RGB, CMYK, Lab or gray colors.
+    Ase,
+    /// a raw list of hexadecimal colors, one per line, as palette sites like Lospec publish them.
+    Hex,
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Format::Plt => "plt",
+            Format::Gpl => "GIMP",
+            Format::JascPal => "JASC-PAL",
+            Format::PaintNet => "Paint.NET",
+            Format::Aco => "ACO",
+            Format::Ase => "ASE",
+            Format::Hex => "hex",
+        })
+    }
+}
+
+impl Format {
+    /// the format a file's extension names, if it's one of ours: `.plt`, `.gpl`, `.pal`, `.txt`, `.aco`, `.ase`
+    /// or `.hex`.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Some(match extension.as_str() {
+            "plt" => Format::Plt,
+            "gpl" => Format::Gpl,
+            "pal" => Format::JascPal,
+            "txt" => Format::PaintNet,
+            "aco" => Format::Aco,
+            "ase" => Format::Ase,
+            "hex" => Format::Hex,
+            _ => return None,
+        })
+    }
+
+    /// the format of a palette file: by its extension if `path` has a known one, otherwise by its contents.
+    /// anything unrecognized is read as a [Format::Plt].
+    /// ```
+    /// # use dither::color::palette::format::Format;
+    /// # use std::path::Path;
+    /// assert_eq!(Format::detect(Some(Path::new("sunset.ASE")), b""), Format::Ase);
+    /// assert_eq!(Format::detect(Some(Path::new("sunset")), b"JASC-PAL\r\n0100\r\n"), Format::JascPal);
+    /// assert_eq!(Format::detect(None, b"; paint.net\nFFFF8000\n"), Format::PaintNet);
+    /// assert_eq!(Format::detect(None, b"ff8000\n141450\n"), Format::Plt);
+    /// ```
+    pub fn detect(path: Option<&Path>, contents: &[u8]) -> Self {
+        if let Some(format) = path.and_then(Format::from_extension) {
+            return format;
+        }
+        if contents.starts_with(b"ASEF") {
+            return Format::Ase;
+        }
+        if contents.starts_with(&[0, 1]) || contents.starts_with(&[0, 2]) {
+            return Format::Aco;
+        }
+        let text = String::from_utf8_lossy(contents);
+        let text = text.trim_start_matches('\u{feff}');
+        if text.starts_with("GIMP Palette") {
+            Format::Gpl
+        } else if text.starts_with("JASC-PAL") {
+            Format::JascPal
+        } else if text.lines().any(|line| line.trim_start().starts_with(';')) {
+            Format::PaintNet
+        } else {
+            Format::Plt
+        }
+    }
+}
+
+/// load the palette file at `path`, [detecting][Format::detect] its format. `.txt` is only a hint, since plenty
+/// of palettes that aren't Paint.NET's end in it: if one can't be read as Paint.NET, it's read by its contents.
+pub fn load(path: &Path) -> Result<Vec<RGB<u8>>, Error> {
+    let contents = std::fs::read(path).map_err(|err| Error::BadFile {
+        path: path.display().to_string(),
+        err,
+    })?;
+    let format = Format::detect(Some(path), &contents);
+    match read(&contents, format) {
+        Err(err) if format == Format::PaintNet => match Format::detect(None, &contents) {
+            Format::PaintNet => Err(err),
+            by_contents => read(&contents, by_contents).map_err(|_| err),
+        },
+        colors => colors,
+    }
+}
+
+/// read a palette in `format`. palettes need at least two colors.
+pub fn read(contents: &[u8], format: Format) -> Result<Vec<RGB<u8>>, Error> {
+    let bad = |reason: String| Error::BadPalette { format, reason };
+    let text = || match std::str::from_utf8(contents) {
+        Ok(text) => Ok(text.trim_start_matches('\u{feff}')),
+        Err(_) => Err(bad("not valid UTF-8 text".to_string())),
+    };
+    let colors = match format {
+        Format::Plt | Format::Hex => return super::parse(text()?),
+        Format::Gpl => read_gpl(text()?).map_err(bad)?,
+        Format::JascPal => read_jasc_pal(text()?).map_err(bad)?,
+        Format::PaintNet => read_paint_net(text()?).map_err(bad)?,
+        Format::Aco => read_aco(&mut Reader(contents)).map_err(bad)?,
+        Format::Ase => read_ase(&mut Reader(contents)).map_err(bad)?,
+    };
+    if colors.len() < 2 {
+        return Err(Error::PaletteTooSmall);
+    }
+    Ok(colors)
+}
+
+/// save `palette` to `path`, in the format its extension names; a [Format::Plt] if it names none.
+/// `name` labels the palette in the formats that have names.
+pub fn save(palette: &Palette, name: &str, path: &Path) -> std::io::Result<()> {
+    let format = Format::from_extension(path).unwrap_or(Format::Plt);
+    std::fs::write(path, write(palette, name, format))
+}
+
+/// write `palette` in `format`: the inverse of [read]. `name` labels the palette in the formats that have names:
+/// GIMP, Paint.NET and `.plt` comments, and ASE groups. colors are named by their hexadecimal values.
+/// ```
+/// # use dither::color::palette::{cga, format::{self, Format}};
+/// let gpl = format::write(&cga::ALL[..2], "CGA", Format::Gpl);
+/// assert_eq!(String::from_utf8(gpl).unwrap(), "GIMP Palette\nName: CGA\n#\n  0   0   0\t000000\n  0   0 170\t0000aa\n");
+/// ```
+pub fn write(palette: &Palette, name: &str, format: Format) -> Vec<u8> {
+    use std::fmt::Write;
+    let mut text = String::new();
+    let decimal = |RGB(r, g, b): RGB<u8>| format!("{:3} {:3} {:3}", r, g, b);
+    match format {
+        Format::Plt => {
+            writeln!(text, "// {}", name).unwrap();
+            for &c in palette {
+                writeln!(text, "0x{:x}", c).unwrap();
+            }
+        }
+        Format::Hex => {
+            for &c in palette {
+                writeln!(text, "{:x}", c).unwrap();
+            }
+        }
+        Format::Gpl => {
+            write!(text, "GIMP Palette\nName: {}\n#\n", name).unwrap();
+            for &c in palette {
+                writeln!(text, "{}\t{:x}", decimal(c), c).unwrap();
+            }
+        }
+        Format::JascPal => {
+            write!(text, "JASC-PAL\r\n0100\r\n{}\r\n", palette.len()).unwrap();
+            for &RGB(r, g, b) in palette {
+                write!(text, "{} {} {}\r\n", r, g, b).unwrap();
+            }
+        }
+        Format::PaintNet => {
+            write!(text, "; {}\r\n", name).unwrap();
+            for &c in palette {
+                write!(text, "FF{:06X}\r\n", c.to_hex()).unwrap();
+            }
+        }
+        Format::Aco => return write_aco(palette),
+        Format::Ase => return write_ase(palette, name),
+    }
+    text.into_bytes()
+}
+
+/// a version 1 swatch file, of RGB colors.
+fn write_aco(palette: &Palette) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend(1u16.to_be_bytes());
+    data.extend((palette.len() as u16).to_be_bytes());
+    for &RGB(r, g, b) in palette {
+        // the color space, RGB.
+        data.extend(0u16.to_be_bytes());
+        // the channels, scaled to 16 bits, then an unused one.
+        for channel in [r, g, b, 0] {
+            data.extend((u16::from(channel) * 257).to_be_bytes());
+        }
+    }
+    data
+}
+
+/// a group of RGB colors, named `name`.
+fn write_ase(palette: &Palette, name: &str) -> Vec<u8> {
+    // UTF-16 and null-terminated, after its length in code units.
+    let utf16 = |s: &str| {
+        let units: Vec<u16> = s.encode_utf16().chain([0]).collect();
+        let mut data = (units.len() as u16).to_be_bytes().to_vec();
+        data.extend(units.iter().flat_map(|u| u.to_be_bytes()));
+        data
+    };
+    let mut data = b"ASEF".to_vec();
+    data.extend([0, 1, 0, 0]);
+    data.extend((palette.len() as u32 + 2).to_be_bytes());
+    let mut block = |kind: u16, contents: Vec<u8>| {
+        data.extend(kind.to_be_bytes());
+        data.extend((contents.len() as u32).to_be_bytes());
+        data.extend(contents);
+    };
+    block(0xc001, utf16(name));
+    for &c in palette {
+        let mut color = utf16(&format!("{:x}", c));
+        color.extend(b"RGB ");
+        let RGB(r, g, b) = c;
+        for channel in [r, g, b] {
+            color.extend((f32::from(channel) / 255.).to_be_bytes());
+        }
+        // a global color.
+        color.extend(0u16.to_be_bytes());
+        block(0x0001, color);
+    }
+    block(0xc002, Vec::new());
+    data
+}
+
+/// parse the decimal `R G B` at the start of `line`, ignoring anything after it.
+fn decimal_rgb(line: &str) -> Result<RGB<u8>, String> {
+    let mut channels = line.split_whitespace().map(str::parse::<u8>);
+    match (channels.next(), channels.next(), channels.next()) {
+        (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => Ok(RGB(r, g, b)),
+        _ => Err(format!(
+            "expected a decimal \"R G B\" color, got \"{}\"",
+            line
+        )),
+    }
+}
+
+fn read_gpl(text: &str) -> Result<Vec<RGB<u8>>, String> {
+    let mut lines = text.lines().map(str::trim);
+    if lines.next() != Some("GIMP Palette") {
+        return Err("missing the \"GIMP Palette\" header".to_string());
+    }
+    lines
+        .filter(|line| {
+            !(line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with("Name:")
+                || line.starts_with("Columns:"))
+        })
+        .map(decimal_rgb)
+        .collect()
+}
+
+fn read_jasc_pal(text: &str) -> Result<Vec<RGB<u8>>, String> {
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    if lines.next() != Some("JASC-PAL") {
+        return Err("missing the \"JASC-PAL\" header".to_string());
+    }
+    let _version = lines.next();
+    let count: usize = match lines.next().map(str::parse) {
+        Some(Ok(count)) => count,
+        _ => return Err("missing the color count".to_string()),
+    };
+    let colors = lines
+        .take(count)
+        .map(decimal_rgb)
+        .collect::<Result<Vec<_>, _>>()?;
+    if colors.len() < count {
+        return Err(format!("expected {} colors, found {}", count, colors.len()));
+    }
+    Ok(colors)
+}
+
+fn read_paint_net(text: &str) -> Result<Vec<RGB<u8>>, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !(line.is_empty() || line.starts_with(';')))
+        .map(|line| {
+            // AARRGGBB, or just RRGGBB.
+            let hex = match line.len() {
+                8 => line.get(2..).unwrap_or(line),
+                _ => line,
+            };
+            match (hex.len(), u32::from_str_radix(hex, 16)) {
+                (6, Ok(rgb)) => Ok(RGB((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)),
+                _ => Err(format!("expected an AARRGGBB color, got \"{}\"", line)),
+            }
+        })
+        .collect()
+}
+
+/// reads big-endian binary data.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < n {
+            return Err("unexpected end of file".to_string());
+        }
+        let (taken, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(taken)
+    }
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+/// the sRGB color of a fraction `0..=1` of each channel.
+fn unit_rgb(r: f64, g: f64, b: f64) -> RGB<u8> {
+    RGB(r, g, b).convert_with(|c| crate::clamp_f64_to_u8(c * 255.))
+}
+
+/// the sRGB color of ink coverages `0..=1`.
+fn unit_cmyk(c: f64, m: f64, y: f64, k: f64) -> RGB<u8> {
+    CMYK(c, m, y, k)
+        .convert_with(|ink| ink * 255.)
+        .to_rgb()
+        .convert_with(crate::clamp_f64_to_u8)
+}
+
+fn lab(l: f64, a: f64, b: f64) -> RGB<u8> {
+    RGB::from_lab([l, a, b]).convert_with(crate::clamp_f64_to_u8)
+}
+
+/// See the "Color Swatches" section of Adobe's Photoshop file format specification. a version 1 section comes
+/// first, and may be followed by a version 2 section that adds names; only the first is read.
+fn read_aco(data: &mut Reader) -> Result<Vec<RGB<u8>>, String> {
+    let version = data.u16()?;
+    if version != 1 && version != 2 {
+        return Err(format!("unknown version {}", version));
+    }
+    let count = data.u16()?;
+    let mut colors = Vec::with_capacity(count.into());
+    for _ in 0..count {
+        let space = data.u16()?;
+        let [w, x, y, z] = [data.u16()?, data.u16()?, data.u16()?, data.u16()?];
+        let unit = |v: u16| f64::from(v) / 65535.;
+        colors.push(match space {
+            0 => unit_rgb(unit(w), unit(x), unit(y)),
+            1 => {
+                let (h, s, v) = (unit(w) * 6., unit(x), unit(y));
+                let chroma = v * s;
+                let mid = chroma * (1. - (h.rem_euclid(2.) - 1.).abs());
+                let (r, g, b) = match h as u32 {
+                    0 => (chroma, mid, 0.),
+                    1 => (mid, chroma, 0.),
+                    2 => (0., chroma, mid),
+                    3 => (0., mid, chroma),
+                    4 => (mid, 0., chroma),
+                    _ => (chroma, 0., mid),
+                };
+                let m = v - chroma;
+                unit_rgb(r + m, g + m, b + m)
+            }
+            // 0 is full coverage.
+            2 => unit_cmyk(1. - unit(w), 1. - unit(x), 1. - unit(y), 1. - unit(z)),
+            7 => lab(
+                f64::from(w) / 100.,
+                f64::from(x as i16) / 100.,
+                f64::from(y as i16) / 100.,
+            ),
+            // 0 is white, and 10000 black.
+            8 => {
+                let gray = 1. - f64::from(w) / 10000.;
+                unit_rgb(gray, gray, gray)
+            }
+            space => return Err(format!("unsupported color space {}", space)),
+        });
+        if version == 2 {
+            let len = data.u32()?;
+            data.take(2 * len as usize)?;
+        }
+    }
+    Ok(colors)
+}
+
+/// See <http://www.selapa.net/swatches/colors/fileformats.php#adobe_ase>.
+fn read_ase(data: &mut Reader) -> Result<Vec<RGB<u8>>, String> {
+    if data.take(4)? != b"ASEF" {
+        return Err("missing the \"ASEF\" signature".to_string());
+    }
+    let _version = (data.u16()?, data.u16()?);
+    let blocks = data.u32()?;
+    let mut colors = Vec::new();
+    for _ in 0..blocks {
+        let kind = data.u16()?;
+        let len = data.u32()?;
+        let mut block = Reader(data.take(len as usize)?);
+        // group starts and ends carry no colors.
+        if kind != 0x0001 {
+            continue;
+        }
+        let name_len = block.u16()?;
+        block.take(2 * usize::from(name_len))?;
+        let model = block.take(4)?;
+        let mut value = || block.f32().map(f64::from);
+        colors.push(match model {
+            b"RGB " => unit_rgb(value()?, value()?, value()?),
+            b"CMYK" => unit_cmyk(value()?, value()?, value()?, value()?),
+            // lightness is stored as a fraction.
+            b"LAB " => lab(value()? * 100., value()?, value()?),
+            b"Gray" => {
+                let gray = value()?;
+                unit_rgb(gray, gray, gray)
+            }
+            model => {
+                return Err(format!(
+                    "unsupported color model \"{}\"",
+                    String::from_utf8_lossy(model)
+                ))
+            }
+        });
+    }
+    Ok(colors)
+}
@@ -0,0 +1,10 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// the four shades of green on the original Game Boy's (DMG) screen, darkest first.
+pub const ALL: &Palette = &[DARKEST, DARK, LIGHT, LIGHTEST];
+
+pub const DARKEST: RGB<u8> = RGB(0x0F, 0x38, 0x0F);
+pub const DARK: RGB<u8> = RGB(0x30, 0x62, 0x30);
+pub const LIGHT: RGB<u8> = RGB(0x8B, 0xAC, 0x0F);
+pub const LIGHTEST: RGB<u8> = RGB(0x9B, 0xBC, 0x0F);
@@ -0,0 +1,39 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// the 16 colors of the classic Macintosh's default 4-bit palette.
+pub const ALL: &Palette = &[
+    WHITE,
+    YELLOW,
+    ORANGE,
+    RED,
+    MAGENTA,
+    PURPLE,
+    BLUE,
+    CYAN,
+    GREEN,
+    DARK_GREEN,
+    BROWN,
+    TAN,
+    LIGHT_GRAY,
+    MEDIUM_GRAY,
+    DARK_GRAY,
+    BLACK,
+];
+
+pub const WHITE: RGB<u8> = RGB(0xFF, 0xFF, 0xFF);
+pub const YELLOW: RGB<u8> = RGB(0xFC, 0xF3, 0x05);
+pub const ORANGE: RGB<u8> = RGB(0xFF, 0x64, 0x03);
+pub const RED: RGB<u8> = RGB(0xDD, 0x09, 0x07);
+pub const MAGENTA: RGB<u8> = RGB(0xF2, 0x08, 0x84);
+pub const PURPLE: RGB<u8> = RGB(0x47, 0x00, 0xA5);
+pub const BLUE: RGB<u8> = RGB(0x00, 0x00, 0xD3);
+pub const CYAN: RGB<u8> = RGB(0x02, 0xAB, 0xEA);
+pub const GREEN: RGB<u8> = RGB(0x1F, 0xB7, 0x14);
+pub const DARK_GREEN: RGB<u8> = RGB(0x00, 0x64, 0x12);
+pub const BROWN: RGB<u8> = RGB(0x56, 0x2C, 0x05);
+pub const TAN: RGB<u8> = RGB(0x90, 0x71, 0x3A);
+pub const LIGHT_GRAY: RGB<u8> = RGB(0xC0, 0xC0, 0xC0);
+pub const MEDIUM_GRAY: RGB<u8> = RGB(0x80, 0x80, 0x80);
+pub const DARK_GRAY: RGB<u8> = RGB(0x40, 0x40, 0x40);
+pub const BLACK: RGB<u8> = RGB(0x00, 0x00, 0x00);
@@ -1,11 +1,52 @@
 pub type Palette = [RGB<u8>];
+use super::distance::ColorDistance;
 use super::{Error, RGB};
+use crate::quantize::{Float, Quantizer};
+use crate::Img;
+use std::sync::Arc;
+/// built-in Amstrad CPC palette
+pub mod amstrad_cpc;
+/// built-in Apple II palette
+pub mod apple2;
+/// built-in Commodore 64 palette
+pub mod c64;
 /// built-in CGA palette; equivalent to cga.plt
 pub mod cga;
 /// built-in CRAYON palette; equivalent to crayon.plt
 pub mod crayon;
+/// built-in EGA palette
+pub mod ega;
+/// built-in e-paper palettes
+pub mod epaper;
+pub mod extract;
+pub mod format;
+/// built-in Game Boy palette
+pub mod gameboy;
+/// built-in Macintosh palette
+pub mod mac16;
+/// built-in MSX palette
+pub mod msx;
+/// built-in NES palette
+pub mod nes;
+/// built-in PICO-8 palette
+pub mod pico8;
+mod search;
+pub mod subset;
+/// built-in teletext palette
+pub mod teletext;
 #[cfg(test)]
 mod tests;
+/// built-in VGA palette
+pub mod vga;
+/// built-in web-safe palette
+pub mod web_safe;
+/// built-in Windows palette
+pub mod windows16;
+/// built-in ZX Spectrum palette
+pub mod zx_spectrum;
+
+use search::Index;
+pub use search::Search;
 
 /// parse a palette, specified as 6-digit hexidecimal RGB values (w/ optional 0x prefix) separated by newlines.
 /// lines consisting entirely of whitespace or starting with `//` are ignored.
@@ -36,31 +77,195 @@ pub fn parse<T: std::iter::FromIterator<RGB<u8>>>(s: &str) -> Result<T, Error> {
             !(line.is_empty() || line.starts_with("//") || line.chars().all(char::is_whitespace))
         })
         .collect();
-    if filtered.len() <= 2 {
+    if filtered.len() < 2 {
         Err(Error::PaletteTooSmall)
     } else {
         filtered.into_iter().map(RGB::<u8>::from_str).collect()
     }
 }
-/// create a quantization function from the specified palette, returning the pair
-/// `(nearest_neighbor, dist_from_neighbor)`
-pub fn quantize(palette: &Palette) -> impl Fn(RGB<f64>) -> (RGB<f64>, RGB<f64>) {
-    // the naive implementation is faster than using a k-d tree for small palettes;
-    // see https://blog.krum.io/k-d-trees/
+/// create a quantizer from the specified palette, which quantizes each pixel to its nearest neighbor in the palette
+/// by [manhattan distance][ColorDistance::Manhattan] and spreads `dist_from_neighbor`. See [quantize_with] for other metrics.
+/// ```
+/// # use dither::prelude::*;
+/// # use dither::color::palette::{self, cga};
+/// # use dither::quantize::Quantizer;
+/// let mut quantize = palette::quantize(cga::ALL);
+/// assert_eq!(quantize.quantize(RGB(0., 0., 160.), (0, 0)), (RGB(0., 0., 170.), RGB(0., 0., -10.)));
+/// ```
+pub fn quantize(palette: &Palette) -> Nearest {
+    quantize_with(palette, ColorDistance::default())
+}
 
-    let palette = palette.to_vec();
-    move |RGB(r0, g0, b0)| {
-        let mut min_abs_err = std::f64::INFINITY;
-        let (mut nearest_neighbor, mut dist_from_neighbor) = (RGB(0., 0., 0.), RGB(0., 0., 0.));
+/// like [quantize], but picking the nearest neighbor by `distance`; i.e, [ColorDistance::DeltaE2000] to match
+/// colors the way they look.
+/// ```
+/// # use dither::prelude::*;
+/// # use dither::color::{distance::ColorDistance, palette::{self, cga}};
+/// # use dither::quantize::Quantizer;
+/// let mut quantize = palette::quantize_with(cga::ALL, ColorDistance::Oklab);
+/// assert_eq!(quantize.nearest(&RGB(0., 0., 160.), (0, 0)), RGB(0., 0., 170.));
+/// ```
+pub fn quantize_with(palette: &Palette, distance: ColorDistance) -> Nearest {
+    let colors: Arc<[RGB<f64>]> = palette.iter().cloned().map(RGB::from).collect();
+    let coordinates: Arc<[[f64; 3]]> = colors.iter().map(|c| distance.coordinates(c)).collect();
+    Nearest {
+        index: Index::build(Search::Auto, &coordinates, distance),
+        colors,
+        coordinates,
+        distance,
+    }
+}
 
-        for RGB(r1, g1, b1) in palette.iter().cloned().map(RGB::<f64>::from) {
-            let abs_err = f64::abs(r0 - r1) + f64::abs(g0 - g1) + f64::abs(b0 - b1);
-            if abs_err < min_abs_err {
-                dist_from_neighbor = RGB(r0 - r1, g0 - g1, b0 - b1);
-                nearest_neighbor = RGB(r1, g1, b1);
-                min_abs_err = abs_err;
+/// Quantizes each pixel to its nearest neighbor in a palette. See [quantize].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nearest {
+    colors: Arc<[RGB<f64>]>,
+    /// the palette in the [ColorDistance]'s coordinates.
+    coordinates: Arc<[[f64; 3]]>,
+    distance: ColorDistance,
+    index: Index,
+}
+
+impl Nearest {
+    /// search the palette with `search`, instead of choosing automatically by the palette's size.
+    /// ```
+    /// # use dither::prelude::*;
+    /// # use dither::color::palette::{self, Search};
+    /// # use dither::quantize::Quantizer;
+    /// let palette: Vec<RGB<u8>> = (0..4096).map(|i| RGB((i % 16 * 17) as u8, (i / 16 % 16 * 17) as u8, (i / 256 * 17) as u8)).collect();
+    /// let mut tree = palette::quantize(&palette).search(Search::KdTree);
+    /// let mut linear = palette::quantize(&palette).search(Search::Linear);
+    /// let p = RGB(100., 20., 240.);
+    /// assert_eq!(tree.nearest(&p, (0, 0)), linear.nearest(&p, (0, 0)));
+    /// ```
+    pub fn search(mut self, search: Search) -> Self {
+        self.index = Index::build(search, &self.coordinates, self.distance);
+        self
+    }
+
+    /// a "smart dither" quantizer for `img`, like [quantize_flat_with], that matches flat pixels with this
+    /// quantizer's metric and [Search] too, so they get the same colors as the pixels around them.
+    /// ```
+    /// # use dither::prelude::*;
+    /// # use dither::color::palette::{self, cga, Search};
+    /// let img: Img<RGB<f64>> = Img::new(vec![RGB(0., 0., 0xAA as f64); 4], 2).unwrap();
+    /// let quantize = palette::quantize(cga::ALL).search(Search::Lut).flat(&img, 0.);
+    /// let got = Ditherer::default().dither(img.clone(), quantize);
+    /// assert_eq!(got, img);
+    /// ```
+    pub fn flat<N: Into<f64> + Clone>(&self, img: &Img<RGB<N>>, tolerance: f64) -> Flat {
+        let (width, height) = img.size();
+        let manhattan = |a: &RGB<f64>, b: &RGB<f64>| ColorDistance::Manhattan.distance(a, b);
+
+        // the solid color for each flat pixel, or None if it should be dithered as usual.
+        let mut solid: Vec<Option<RGB<f64>>> = Vec::with_capacity(img.len());
+        for y in 0..height {
+            for x in 0..width {
+                let at = |x, y| img[(x, y)].clone().convert_with(Into::into);
+                let p = at(x, y);
+                let nearest_neighbor = self.find(&p);
+                let is_flat = manhattan(&p, &nearest_neighbor) <= tolerance
+                    || (y.saturating_sub(1)..=u32::min(y + 1, height - 1)).all(|ny| {
+                        (x.saturating_sub(1)..=u32::min(x + 1, width - 1))
+                            .all(|nx| manhattan(&p, &at(nx, ny)) <= tolerance)
+                    });
+                solid.push(if is_flat {
+                    Some(nearest_neighbor)
+                } else {
+                    None
+                });
             }
         }
-        (nearest_neighbor, dist_from_neighbor)
+        Flat {
+            nearest: self.clone(),
+            solid: solid.into(),
+            width: width as usize,
+        }
+    }
+
+    /// the palette color nearest to `p`.
+    fn find(&self, p: &RGB<f64>) -> RGB<f64> {
+        let nearest_neighbor = self.index.find_rgb(&self.coordinates, self.distance, p);
+        self.colors[nearest_neighbor].clone()
+    }
+}
+
+impl<N: Float> Quantizer<RGB<N>> for Nearest {
+    fn nearest(&mut self, p: &RGB<N>, _: (usize, usize)) -> RGB<N> {
+        self.find(&p.clone().convert_with(Into::into))
+            .convert_with(N::from_f64)
+    }
+    fn error(&mut self, p: RGB<N>, nearest: &RGB<N>, _: (usize, usize)) -> RGB<N> {
+        let (RGB(r0, g0, b0), RGB(r1, g1, b1)) = (p, nearest.clone());
+        let diff = |c0: N, c1: N| N::from_f64(c0.into() - c1.into());
+        RGB(diff(r0, r1), diff(g0, g1), diff(b0, b1))
+    }
+}
+
+/// create a "smart dither" quantizer from the specified palette, which suppresses dithering in flat regions of `img`.
+///
+/// `img` can be in any working precision, like the [RGB<u8>] it was loaded as.
+/// a pixel is flat if its original value is within `tolerance` (manhattan distance) of a palette color, or of all of its neighbors.
+/// flat pixels are quantized to the palette color nearest their original value, ignoring carried error and spreading none of their own,
+/// so dithering only shows up in real gradients. a `tolerance` of `0.` only suppresses exactly representable or perfectly flat pixels.
+///
+/// the quantizer goes by each pixel's position, so it can be used on the image in any order, or in parallel.
+/// See [quantize_flat_with] to match colors by another [ColorDistance].
+/// ```
+/// # use dither::prelude::*;
+/// # use dither::color::palette::{self, cga};
+/// let img: Img<RGB<f64>> = Img::new(vec![RGB(0., 0., 0xAA as f64); 4], 2).unwrap();
+/// let quantize = palette::quantize_flat(&img, cga::ALL, 0.);
+/// let got = Ditherer::default().dither(img.clone(), quantize);
+/// assert_eq!(got, img);
+/// ```
+pub fn quantize_flat<N: Into<f64> + Clone>(
+    img: &Img<RGB<N>>,
+    palette: &Palette,
+    tolerance: f64,
+) -> Flat {
+    quantize_flat_with(img, palette, tolerance, ColorDistance::default())
+}
+
+/// like [quantize_flat], but picking the nearest neighbor by `distance`. `tolerance` is still a manhattan distance.
+/// See [Nearest::flat] to search the palette another way.
+pub fn quantize_flat_with<N: Into<f64> + Clone>(
+    img: &Img<RGB<N>>,
+    palette: &Palette,
+    tolerance: f64,
+    distance: ColorDistance,
+) -> Flat {
+    quantize_with(palette, distance).flat(img, tolerance)
+}
+
+/// A "smart dither" quantizer, which leaves flat regions of an image undithered. See [quantize_flat].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flat {
+    nearest: Nearest,
+    solid: Arc<[Option<RGB<f64>>]>,
+    width: usize,
+}
+
+impl Flat {
+    fn solid(&self, (x, y): (usize, usize)) -> Option<RGB<f64>> {
+        self.solid.get(y * self.width + x).cloned().flatten()
+    }
+}
+
+impl<N: Float> Quantizer<RGB<N>> for Flat {
+    fn nearest(&mut self, p: &RGB<N>, at: (usize, usize)) -> RGB<N> {
+        match self.solid(at) {
+            Some(nearest_neighbor) => nearest_neighbor.convert_with(N::from_f64),
+            None => self.nearest.nearest(p, at),
+        }
+    }
+    fn error(&mut self, p: RGB<N>, nearest: &RGB<N>, at: (usize, usize)) -> RGB<N> {
+        match self.solid(at) {
+            Some(_) => RGB(N::from_f64(0.), N::from_f64(0.), N::from_f64(0.)),
+            None => self.nearest.error(p, nearest, at),
+        }
+    }
+    fn is_solid(&self, at: (usize, usize)) -> bool {
+        self.solid(at).is_some()
     }
 }
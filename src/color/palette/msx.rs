@@ -0,0 +1,37 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// the 15 colors of the MSX's TMS9918 video chip, in its color number order after 0, which is transparent.
+pub const ALL: &Palette = &[
+    BLACK,
+    MEDIUM_GREEN,
+    LIGHT_GREEN,
+    DARK_BLUE,
+    LIGHT_BLUE,
+    DARK_RED,
+    CYAN,
+    MEDIUM_RED,
+    LIGHT_RED,
+    DARK_YELLOW,
+    LIGHT_YELLOW,
+    DARK_GREEN,
+    MAGENTA,
+    GRAY,
+    WHITE,
+];
+
+pub const BLACK: RGB<u8> = RGB(0x00, 0x00, 0x00);
+pub const MEDIUM_GREEN: RGB<u8> = RGB(0x3E, 0xB8, 0x49);
+pub const LIGHT_GREEN: RGB<u8> = RGB(0x74, 0xD0, 0x7D);
+pub const DARK_BLUE: RGB<u8> = RGB(0x59, 0x55, 0xE0);
+pub const LIGHT_BLUE: RGB<u8> = RGB(0x80, 0x76, 0xF1);
+pub const DARK_RED: RGB<u8> = RGB(0xB9, 0x5E, 0x51);
+pub const CYAN: RGB<u8> = RGB(0x65, 0xDB, 0xEF);
+pub const MEDIUM_RED: RGB<u8> = RGB(0xDB, 0x65, 0x59);
+pub const LIGHT_RED: RGB<u8> = RGB(0xFF, 0x89, 0x7D);
+pub const DARK_YELLOW: RGB<u8> = RGB(0xCC, 0xC3, 0x5E);
+pub const LIGHT_YELLOW: RGB<u8> = RGB(0xDE, 0xD0, 0x87);
+pub const DARK_GREEN: RGB<u8> = RGB(0x3A, 0xA2, 0x41);
+pub const MAGENTA: RGB<u8> = RGB(0xB7, 0x66, 0xB5);
+pub const GRAY: RGB<u8> = RGB(0xCC, 0xCC, 0xCC);
+pub const WHITE: RGB<u8> = RGB(0xFF, 0xFF, 0xFF);
@@ -0,0 +1,62 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// the 55 distinct colors of the NES's 2C02 picture processor, in its color number order, as emulators commonly
+/// render them. its 64 color numbers repeat black several times.
+pub const ALL: &Palette = &[
+    RGB(0x7C, 0x7C, 0x7C),
+    RGB(0x00, 0x00, 0xFC),
+    RGB(0x00, 0x00, 0xBC),
+    RGB(0x44, 0x28, 0xBC),
+    RGB(0x94, 0x00, 0x84),
+    RGB(0xA8, 0x00, 0x20),
+    RGB(0xA8, 0x10, 0x00),
+    RGB(0x88, 0x14, 0x00),
+    RGB(0x50, 0x30, 0x00),
+    RGB(0x00, 0x78, 0x00),
+    RGB(0x00, 0x68, 0x00),
+    RGB(0x00, 0x58, 0x00),
+    RGB(0x00, 0x40, 0x58),
+    RGB(0x00, 0x00, 0x00),
+    RGB(0xBC, 0xBC, 0xBC),
+    RGB(0x00, 0x78, 0xF8),
+    RGB(0x00, 0x58, 0xF8),
+    RGB(0x68, 0x44, 0xFC),
+    RGB(0xD8, 0x00, 0xCC),
+    RGB(0xE4, 0x00, 0x58),
+    RGB(0xF8, 0x38, 0x00),
+    RGB(0xE4, 0x5C, 0x10),
+    RGB(0xAC, 0x7C, 0x00),
+    RGB(0x00, 0xB8, 0x00),
+    RGB(0x00, 0xA8, 0x00),
+    RGB(0x00, 0xA8, 0x44),
+    RGB(0x00, 0x88, 0x88),
+    RGB(0xF8, 0xF8, 0xF8),
+    RGB(0x3C, 0xBC, 0xFC),
+    RGB(0x68, 0x88, 0xFC),
+    RGB(0x98, 0x78, 0xF8),
+    RGB(0xF8, 0x78, 0xF8),
+    RGB(0xF8, 0x58, 0x98),
+    RGB(0xF8, 0x78, 0x58),
+    RGB(0xFC, 0xA0, 0x44),
+    RGB(0xF8, 0xB8, 0x00),
+    RGB(0xB8, 0xF8, 0x18),
+    RGB(0x58, 0xD8, 0x54),
+    RGB(0x58, 0xF8, 0x98),
+    RGB(0x00, 0xE8, 0xD8),
+    RGB(0x78, 0x78, 0x78),
+    RGB(0xFC, 0xFC, 0xFC),
+    RGB(0xA4, 0xE4, 0xFC),
+    RGB(0xB8, 0xB8, 0xF8),
+    RGB(0xD8, 0xB8, 0xF8),
+    RGB(0xF8, 0xB8, 0xF8),
+    RGB(0xF8, 0xA4, 0xC0),
+    RGB(0xF0, 0xD0, 0xB0),
+    RGB(0xFC, 0xE0, 0xA8),
+    RGB(0xF8, 0xD8, 0x78),
+    RGB(0xD8, 0xF8, 0x78),
+    RGB(0xB8, 0xF8, 0xB8),
+    RGB(0xB8, 0xF8, 0xD8),
+    RGB(0x00, 0xFC, 0xFC),
+    RGB(0xF8, 0xD8, 0xF8),
+];
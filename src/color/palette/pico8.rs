@@ -0,0 +1,39 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// the 16 colors of the PICO-8 fantasy console, in its color number order.
+pub const ALL: &Palette = &[
+    BLACK,
+    DARK_BLUE,
+    DARK_PURPLE,
+    DARK_GREEN,
+    BROWN,
+    DARK_GRAY,
+    LIGHT_GRAY,
+    WHITE,
+    RED,
+    ORANGE,
+    YELLOW,
+    GREEN,
+    BLUE,
+    LAVENDER,
+    PINK,
+    LIGHT_PEACH,
+];
+
+pub const BLACK: RGB<u8> = RGB(0x00, 0x00, 0x00);
+pub const DARK_BLUE: RGB<u8> = RGB(0x1D, 0x2B, 0x53);
+pub const DARK_PURPLE: RGB<u8> = RGB(0x7E, 0x25, 0x53);
+pub const DARK_GREEN: RGB<u8> = RGB(0x00, 0x87, 0x51);
+pub const BROWN: RGB<u8> = RGB(0xAB, 0x52, 0x36);
+pub const DARK_GRAY: RGB<u8> = RGB(0x5F, 0x57, 0x4F);
+pub const LIGHT_GRAY: RGB<u8> = RGB(0xC2, 0xC3, 0xC7);
+pub const WHITE: RGB<u8> = RGB(0xFF, 0xF1, 0xE8);
+pub const RED: RGB<u8> = RGB(0xFF, 0x00, 0x4D);
+pub const ORANGE: RGB<u8> = RGB(0xFF, 0xA3, 0x00);
+pub const YELLOW: RGB<u8> = RGB(0xFF, 0xEC, 0x27);
+pub const GREEN: RGB<u8> = RGB(0x00, 0xE4, 0x36);
+pub const BLUE: RGB<u8> = RGB(0x29, 0xAD, 0xFF);
+pub const LAVENDER: RGB<u8> = RGB(0x83, 0x76, 0x9C);
+pub const PINK: RGB<u8> = RGB(0xFF, 0x77, 0xA8);
+pub const LIGHT_PEACH: RGB<u8> = RGB(0xFF, 0xCC, 0xAA);
@@ -0,0 +1,312 @@
+//! Nearest-neighbor search over a palette's [coordinates][ColorDistance::coordinates]. See [Search].
+use super::super::distance::ColorDistance;
+use super::super::RGB;
+use std::sync::Arc;
+
+/// How a [Nearest][super::Nearest] quantizer searches its palette. See [Nearest::search][super::Nearest::search].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Search {
+    /// a [Search::Linear] scan for small palettes, and a [Search::KdTree] for large ones, where the metric allows.
+    #[default]
+    Auto,
+    /// compare every color, ranking them in SIMD batches for metrics that are a norm of the coordinates.
+    /// works with any [ColorDistance], and is fastest for up to a few dozen colors.
+    Linear,
+    /// a [k-d tree](https://en.wikipedia.org/wiki/K-d_tree): logarithmic time for large palettes. exact, like
+    /// [Search::Linear], but needs a metric that's a norm of the coordinates: it falls back to a linear scan for
+    /// [ColorDistance::Redmean] and [ColorDistance::DeltaE2000].
+    KdTree,
+    /// a precomputed 64x64x64 lookup table of the nearest color to each cell of sRGB: constant time, for 1MB
+    /// and a one-off cost to build. approximate: each pixel gets the nearest color to the center of its cell,
+    /// which is up to 2 away in each channel.
+    Lut,
+}
+
+/// the largest palette [Search::Auto] scans linearly.
+const LINEAR_MAX: usize = 64;
+
+/// the bits per channel of a [Search::Lut].
+const LUT_BITS: u32 = 6;
+
+/// how many colors [Lanes::rank] ranks at once.
+const LANES: usize = 2;
+
+/// a built [Search].
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Index {
+    Linear(Arc<Lanes>),
+    KdTree(Arc<KdTree>),
+    Lut(Arc<[u32]>),
+}
+
+impl Index {
+    pub(super) fn build(search: Search, coordinates: &[[f64; 3]], distance: ColorDistance) -> Self {
+        let use_tree = is_norm(distance)
+            && match search {
+                Search::Auto => coordinates.len() > LINEAR_MAX,
+                Search::KdTree | Search::Lut => true,
+                Search::Linear => false,
+            };
+        let index = if use_tree {
+            Index::KdTree(Arc::new(KdTree::new(coordinates)))
+        } else {
+            Index::Linear(Arc::new(Lanes::new(coordinates)))
+        };
+        if search != Search::Lut {
+            return index;
+        }
+        // the center of each cell, i.e, 1.5 for the cell of 0, 1, 2 and 3.
+        let center = |cell: usize| {
+            ((cell << (8 - LUT_BITS)) as f64) + ((1 << (8 - LUT_BITS)) - 1) as f64 / 2.
+        };
+        let cells = 1 << LUT_BITS;
+        let mut table = Vec::with_capacity(cells * cells * cells);
+        for r in 0..cells {
+            for g in 0..cells {
+                for b in 0..cells {
+                    let p = distance.coordinates(&RGB(center(r), center(g), center(b)));
+                    table.push(index.find(coordinates, distance, &p) as u32);
+                }
+            }
+        }
+        Index::Lut(table.into())
+    }
+
+    /// the index of the color nearest to `rgb`.
+    pub(super) fn find_rgb(
+        &self,
+        coordinates: &[[f64; 3]],
+        distance: ColorDistance,
+        rgb: &RGB<f64>,
+    ) -> usize {
+        match self {
+            Index::Lut(table) => {
+                let cell = |c: f64| (crate::clamp_f64_to_u8(c) >> (8 - LUT_BITS)) as usize;
+                let RGB(r, g, b) = rgb;
+                table[(cell(*r) << (2 * LUT_BITS)) | (cell(*g) << LUT_BITS) | cell(*b)] as usize
+            }
+            index => index.find(coordinates, distance, &distance.coordinates(rgb)),
+        }
+    }
+
+    /// the index of the color nearest to `p`. the first one, if there's a tie.
+    fn find(&self, coordinates: &[[f64; 3]], distance: ColorDistance, p: &[f64; 3]) -> usize {
+        match self {
+            Index::KdTree(tree) => tree.find(distance, p),
+            Index::Linear(lanes) if is_norm(distance) => lanes.find(distance, p),
+            _ => {
+                let (mut nearest, mut min_distance) = (0, f64::INFINITY);
+                for (i, c) in coordinates.iter().enumerate() {
+                    let d = distance.between(p, c);
+                    if d < min_distance {
+                        nearest = i;
+                        min_distance = d;
+                    }
+                }
+                nearest
+            }
+        }
+    }
+}
+
+/// whether `distance` is a norm of the difference between coordinates, so a difference along one axis is a lower
+/// bound on the whole distance.
+fn is_norm(distance: ColorDistance) -> bool {
+    !matches!(distance, ColorDistance::Redmean | ColorDistance::DeltaE2000)
+}
+
+/// a value that orders colors by a norm `distance`, and is cheaper than it: i.e, the squared euclidean distance.
+fn rank(distance: ColorDistance, d: [f64; 3]) -> f64 {
+    match distance {
+        ColorDistance::Manhattan => d[0].abs() + d[1].abs() + d[2].abs(),
+        _ => d[0] * d[0] + d[1] * d[1] + d[2] * d[2],
+    }
+}
+
+/// A palette's coordinates laid out by axis, padded with zeroes to a whole number of [LANES] colors,
+/// so a linear scan can [rank][Lanes::rank] them [LANES] at a time.
+#[derive(Debug, PartialEq)]
+pub(super) struct Lanes {
+    axes: [Vec<f64>; 3],
+    len: usize,
+}
+
+impl Lanes {
+    fn new(coordinates: &[[f64; 3]]) -> Self {
+        let padded = coordinates.len().next_multiple_of(LANES);
+        let axes = [0, 1, 2].map(|axis| {
+            let mut values: Vec<f64> = coordinates.iter().map(|c| c[axis]).collect();
+            values.resize(padded, 0.);
+            values
+        });
+        Lanes {
+            axes,
+            len: coordinates.len(),
+        }
+    }
+
+    /// the index of the color nearest to `p` by a norm `distance`: rank the palette in batches, then pick the best.
+    fn find(&self, distance: ColorDistance, p: &[f64; 3]) -> usize {
+        const BATCH: usize = 64;
+        let (mut nearest, mut min_rank) = (0, f64::INFINITY);
+        let mut ranks = [0.; BATCH];
+        for start in (0..self.len).step_by(BATCH) {
+            let end = usize::min(start + BATCH, self.len);
+            let ranks = &mut ranks[..end.next_multiple_of(LANES) - start];
+            self.rank(distance, p, start, ranks);
+            for (i, &r) in ranks[..end - start].iter().enumerate() {
+                if r < min_rank {
+                    nearest = start + i;
+                    min_rank = r;
+                }
+            }
+        }
+        nearest
+    }
+
+    /// fill `ranks` with the [rank] of the colors from `start` on, two at a time with SSE2.
+    /// the results are exactly the scalar [rank]'s: the same operations, in the same order.
+    #[cfg(target_arch = "x86_64")]
+    fn rank(&self, distance: ColorDistance, p: &[f64; 3], start: usize, ranks: &mut [f64]) {
+        use std::arch::x86_64::*;
+        let axes = self
+            .axes
+            .each_ref()
+            .map(|axis| &axis[start..start + ranks.len()]);
+        assert!(ranks.len().is_multiple_of(LANES));
+        // SAFETY: SSE2 is part of the x86_64 baseline, and every load and store is of two values within
+        // slices of the same length, a multiple of two.
+        unsafe {
+            let p = p.map(|c| _mm_set1_pd(c));
+            let sign = _mm_set1_pd(-0.);
+            for i in (0..ranks.len()).step_by(LANES) {
+                let d = [0, 1, 2].map(|a| _mm_sub_pd(p[a], _mm_loadu_pd(axes[a].as_ptr().add(i))));
+                let r = match distance {
+                    ColorDistance::Manhattan => {
+                        let [x, y, z] = d.map(|d| _mm_andnot_pd(sign, d));
+                        _mm_add_pd(_mm_add_pd(x, y), z)
+                    }
+                    _ => {
+                        let [x, y, z] = d.map(|d| _mm_mul_pd(d, d));
+                        _mm_add_pd(_mm_add_pd(x, y), z)
+                    }
+                };
+                _mm_storeu_pd(ranks.as_mut_ptr().add(i), r);
+            }
+        }
+    }
+
+    /// fill `ranks` with the [rank] of the colors from `start` on.
+    #[cfg(not(target_arch = "x86_64"))]
+    fn rank(&self, distance: ColorDistance, p: &[f64; 3], start: usize, ranks: &mut [f64]) {
+        for (i, r) in ranks.iter_mut().enumerate() {
+            let c = self.axes.each_ref().map(|axis| axis[start + i]);
+            *r = rank(distance, [p[0] - c[0], p[1] - c[1], p[2] - c[2]]);
+        }
+    }
+}
+
+/// A k-d tree over a palette, stored implicitly: each node is the median of its slice of `points`,
+/// split along `axes[node]`, with the nodes before it on its left and the ones after it on its right.
+/// slices of up to [KdTree::LEAF] points are leaves, and scanned linearly.
+#[derive(Debug, PartialEq)]
+pub(super) struct KdTree {
+    /// the palette's coordinates, in tree order.
+    points: Vec<[f64; 3]>,
+    /// the palette index of each point.
+    order: Vec<usize>,
+    axes: Vec<usize>,
+}
+
+impl KdTree {
+    const LEAF: usize = 8;
+
+    fn new(coordinates: &[[f64; 3]]) -> Self {
+        let mut order: Vec<usize> = (0..coordinates.len()).collect();
+        let mut axes = vec![0; coordinates.len()];
+        Self::build(coordinates, &mut order, &mut axes);
+        KdTree {
+            points: order.iter().map(|&i| coordinates[i]).collect(),
+            order,
+            axes,
+        }
+    }
+
+    fn build(coordinates: &[[f64; 3]], order: &mut [usize], axes: &mut [usize]) {
+        if order.len() <= Self::LEAF {
+            return;
+        }
+        // split along the axis the colors are most spread out on.
+        let spread = |axis: usize| {
+            let values = order.iter().map(|&i| coordinates[i][axis]);
+            values.clone().fold(f64::NEG_INFINITY, f64::max) - values.fold(f64::INFINITY, f64::min)
+        };
+        let axis = (0..3)
+            .max_by(|&a, &b| spread(a).total_cmp(&spread(b)))
+            .unwrap();
+        let mid = order.len() / 2;
+        order.select_nth_unstable_by(mid, |&a, &b| {
+            coordinates[a][axis].total_cmp(&coordinates[b][axis])
+        });
+        axes[mid] = axis;
+        let (left, right) = order.split_at_mut(mid);
+        let (left_axes, right_axes) = axes.split_at_mut(mid);
+        Self::build(coordinates, left, left_axes);
+        Self::build(coordinates, &mut right[1..], &mut right_axes[1..]);
+    }
+
+    fn find(&self, distance: ColorDistance, p: &[f64; 3]) -> usize {
+        let mut best = (f64::INFINITY, usize::MAX);
+        // a closure per metric, so the search is specialized to it.
+        match distance {
+            ColorDistance::Manhattan => {
+                let manhattan = |d| rank(ColorDistance::Manhattan, d);
+                self.search(&manhattan, p, 0, self.points.len(), &mut best)
+            }
+            _ => {
+                let squared = |d| rank(ColorDistance::Euclidean, d);
+                self.search(&squared, p, 0, self.points.len(), &mut best)
+            }
+        }
+        best.1
+    }
+
+    /// search the subtree of `points[lo..hi]`, updating `best`: the `(rank, index)` of the nearest color so far.
+    fn search(
+        &self,
+        rank: &impl Fn([f64; 3]) -> f64,
+        p: &[f64; 3],
+        lo: usize,
+        hi: usize,
+        best: &mut (f64, usize),
+    ) {
+        let mut visit = |at: usize| {
+            let (c, i) = (&self.points[at], self.order[at]);
+            let r = rank([p[0] - c[0], p[1] - c[1], p[2] - c[2]]);
+            // ties go to the first color in the palette, like a linear scan.
+            if r < best.0 || (r == best.0 && i < best.1) {
+                *best = (r, i);
+            }
+        };
+        if hi - lo <= Self::LEAF {
+            (lo..hi).for_each(visit);
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        visit(mid);
+        let axis = self.axes[mid];
+        let diff = p[axis] - self.points[mid][axis];
+        let (near, far) = if diff < 0. {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+        self.search(rank, p, near.0, near.1, best);
+        // the far side is at least `diff` away along this axis.
+        let mut bound = [0.; 3];
+        bound[axis] = diff;
+        if rank(bound) <= best.0 {
+            self.search(rank, p, far.0, far.1, best);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Palette subsets: pick the best K colors of a fixed master palette for an image, for hardware with a fixed
+//! palette but a smaller per-image budget. Corresponds to [Opt][crate::Opt] `--subset`.
+//!
+//! The best subset is the one that leaves the least squared distance between each pixel and its nearest color,
+//! as measured by a [ColorDistance]. Small searches, like 4 of CGA's 16 colors, try every subset; larger ones
+//! grow a subset greedily, then swap colors in and out while that improves it.
+//! ```
+//! # use dither::prelude::*;
+//! # use dither::color::distance::ColorDistance;
+//! # use dither::color::palette::{cga, subset};
+//! // a sunset: reds, yellows and a little black.
+//! let img: Img<RGB<u8>> = Img::new((0..64).map(|i| match i % 8 { 0 => cga::BLACK, 1..=4 => RGB(200, 20, 10), _ => RGB(250, 240, 90) }), 8).unwrap();
+//! let best = subset::select(&img, cga::ALL, 3, ColorDistance::Oklab);
+//! assert_eq!(best, vec![cga::BLACK, cga::RED, cga::YELLOW]);
+//! ```
+use super::extract::{self, Entry};
+use super::Palette;
+use crate::color::distance::ColorDistance;
+use crate::color::RGB;
+use crate::Img;
+
+/// the most subsets [select] tries exhaustively, rather than searching locally.
+const EXHAUSTIVE_MAX: u64 = 20_000;
+
+/// the best `colors` colors of `palette` for `img`, by `distance`, in palette order.
+/// palettes with no more than `colors` colors are returned whole.
+pub fn select<N: Into<f64> + Clone>(
+    img: &Img<RGB<N>>,
+    palette: &Palette,
+    colors: usize,
+    distance: ColorDistance,
+) -> Vec<RGB<u8>> {
+    if colors >= palette.len() {
+        return palette.to_vec();
+    }
+    // the image's colors, averaged into 32x32x32 cells so large images stay cheap.
+    let mut histogram = extract::histogram(img);
+    histogram.sort_by_key(|&(c, _)| c.map(|c| c >> 3));
+    let cells: Vec<(f64, [f64; 3])> = histogram
+        .chunk_by(|(a, _), (b, _)| a.map(|c| c >> 3) == b.map(|c| c >> 3))
+        .map(|cell: &[Entry]| {
+            let count: u64 = cell.iter().map(|&(_, n)| n).sum();
+            let mean = RGB::from(extract::mean(cell));
+            (count as f64, distance.coordinates(&mean))
+        })
+        .collect();
+    // the cost of matching each cell to each palette color.
+    let palette_coordinates: Vec<[f64; 3]> = palette
+        .iter()
+        .map(|&c| distance.coordinates(&RGB::from(c)))
+        .collect();
+    let costs: Vec<Vec<f64>> = cells
+        .iter()
+        .map(|(count, p)| {
+            palette_coordinates
+                .iter()
+                .map(|c| count * distance.between(p, c).powi(2))
+                .collect()
+        })
+        .collect();
+
+    let mut chosen = if binomial(palette.len(), colors) <= EXHAUSTIVE_MAX {
+        exhaustive(&costs, palette.len(), colors)
+    } else {
+        local(&costs, palette.len(), colors)
+    };
+    chosen.sort_unstable();
+    chosen.into_iter().map(|i| palette[i]).collect()
+}
+
+/// `n` choose `k`, saturating.
+fn binomial(n: usize, k: usize) -> u64 {
+    let k = k.min(n - k) as u64;
+    (0..k).fold(1u64, |acc, i| acc.saturating_mul(n as u64 - i) / (i + 1))
+}
+
+/// the total cost of matching every cell to its nearest color in `chosen`.
+fn total(costs: &[Vec<f64>], chosen: &[usize]) -> f64 {
+    costs
+        .iter()
+        .map(|cell| {
+            chosen
+                .iter()
+                .map(|&i| cell[i])
+                .fold(f64::INFINITY, f64::min)
+        })
+        .sum()
+}
+
+/// try every subset of `k` of the `n` colors.
+fn exhaustive(costs: &[Vec<f64>], n: usize, k: usize) -> Vec<usize> {
+    let mut subset: Vec<usize> = (0..k).collect();
+    let (mut best, mut best_cost) = (subset.clone(), total(costs, &subset));
+    loop {
+        // the next subset in lexicographic order: bump the last index that can go higher, and reset the rest.
+        let Some(i) = (0..k).rev().find(|&i| subset[i] < n - k + i) else {
+            return best;
+        };
+        subset[i] += 1;
+        for j in i + 1..k {
+            subset[j] = subset[j - 1] + 1;
+        }
+        let cost = total(costs, &subset);
+        if cost < best_cost {
+            (best, best_cost) = (subset.clone(), cost);
+        }
+    }
+}
+
+/// grow a subset of `k` of the `n` colors greedily, then make the best swap of a chosen color for an unchosen
+/// one until none helps: the partitioning-around-medoids heuristic.
+fn local(costs: &[Vec<f64>], n: usize, k: usize) -> Vec<usize> {
+    let mut chosen: Vec<usize> = Vec::with_capacity(k);
+    // each cell's cost to its nearest chosen color so far.
+    let mut nearest = vec![f64::INFINITY; costs.len()];
+    while chosen.len() < k {
+        let added = |i: usize| -> f64 {
+            costs
+                .iter()
+                .zip(&nearest)
+                .map(|(cell, &d)| d.min(cell[i]))
+                .sum()
+        };
+        let next = (0..n)
+            .filter(|i| !chosen.contains(i))
+            .map(|i| (added(i), i))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap()
+            .1;
+        for (d, cell) in nearest.iter_mut().zip(costs) {
+            *d = d.min(cell[next]);
+        }
+        chosen.push(next);
+    }
+
+    let mut cost = total(costs, &chosen);
+    loop {
+        // each cell's nearest and second-nearest chosen costs, and which slot of `chosen` is nearest, so each swap
+        // can be scored in one pass over the cells.
+        let nearest: Vec<(usize, f64, f64)> = costs
+            .iter()
+            .map(|cell| {
+                let (mut slot, mut first, mut second) = (0, f64::INFINITY, f64::INFINITY);
+                for (s, &i) in chosen.iter().enumerate() {
+                    if cell[i] < first {
+                        (slot, first, second) = (s, cell[i], first);
+                    } else if cell[i] < second {
+                        second = cell[i];
+                    }
+                }
+                (slot, first, second)
+            })
+            .collect();
+        let mut best: Option<(f64, usize, usize)> = None;
+        for slot in 0..k {
+            for candidate in (0..n).filter(|i| !chosen.contains(i)) {
+                let swapped: f64 = costs
+                    .iter()
+                    .zip(&nearest)
+                    .map(|(cell, &(s, first, second))| {
+                        let kept = if s == slot { second } else { first };
+                        kept.min(cell[candidate])
+                    })
+                    .sum();
+                if swapped < best.map_or(cost, |(c, ..)| c) {
+                    best = Some((swapped, slot, candidate));
+                }
+            }
+        }
+        match best {
+            // demand a real improvement, so rounding can't swap forever.
+            Some((swapped, slot, candidate)) if swapped < cost * (1. - 1e-9) => {
+                chosen[slot] = candidate;
+                cost = swapped;
+            }
+            _ => return chosen,
+        }
+    }
+}
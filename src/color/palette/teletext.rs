@@ -0,0 +1,14 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// the 8 colors of teletext, in its color number order: every combination of full red, green and blue.
+pub const ALL: &Palette = &[BLACK, RED, GREEN, YELLOW, BLUE, MAGENTA, CYAN, WHITE];
+
+pub const BLACK: RGB<u8> = RGB(0x00, 0x00, 0x00);
+pub const RED: RGB<u8> = RGB(0xFF, 0x00, 0x00);
+pub const GREEN: RGB<u8> = RGB(0x00, 0xFF, 0x00);
+pub const YELLOW: RGB<u8> = RGB(0xFF, 0xFF, 0x00);
+pub const BLUE: RGB<u8> = RGB(0x00, 0x00, 0xFF);
+pub const MAGENTA: RGB<u8> = RGB(0xFF, 0x00, 0xFF);
+pub const CYAN: RGB<u8> = RGB(0x00, 0xFF, 0xFF);
+pub const WHITE: RGB<u8> = RGB(0xFF, 0xFF, 0xFF);
@@ -16,6 +16,12 @@ fn parse() {
         ("LigHT_CYAN", Ok(Mode::SingleColor(cga::LIGHT_CYAN))),
         ("cga", Ok(Mode::CGA)),
         ("cRaYoN", Ok(Mode::CRAYON)),
+        ("auto:16", Ok(Mode::Auto { colors: 16 })),
+        ("AUTO:1", Err(Error::PaletteTooSmall)),
+        (
+            "auto:many",
+            Err(Error::UnknownOption("auto:many".to_string())),
+        ),
         (GARBAGE, Err(Error::UnknownOption(GARBAGE.to_string()))),
     ];
     for (s, want) in tt {
@@ -62,3 +68,353 @@ fn parse() {
     }
     std::fs::remove_file(input).unwrap();
 }
+
+#[test]
+fn quantize_flat() {
+    use crate::prelude::*;
+    // a flat cga::BLUE background on the left, a gray gradient on the right.
+    let (width, height) = (6, 4);
+    let mut buf = Vec::new();
+    for _ in 0..height {
+        for x in 0..width {
+            buf.push(match x {
+                0..=2 => RGB::from(cga::BLUE),
+                x => RGB(40. * x as f64, 40. * x as f64, 40. * x as f64),
+            });
+        }
+    }
+    let img = Img::new(buf, width).unwrap();
+
+    let smart = crate::ditherer::FLOYD_STEINBERG
+        .dither(img.clone(), super::quantize_flat(&img, cga::ALL, 0.));
+    for y in 0..height {
+        for x in 0..3 {
+            assert_eq!(smart[(x, y)], RGB::from(cga::BLUE));
+        }
+    }
+    // the gradient is still dithered as usual.
+    let naive = crate::ditherer::FLOYD_STEINBERG.dither(img.clone(), super::quantize(cga::ALL));
+    assert_eq!(smart[(3, 0)], naive[(3, 0)]);
+
+    // the smart quantizer goes by position, not call order, so it's safe to share between threads.
+    let parallel = crate::ditherer::FLOYD_STEINBERG.dither_parallel(
+        img.clone(),
+        super::quantize_flat(&img, cga::ALL, 0.),
+        3,
+    );
+    assert_eq!(smart, parallel);
+
+    // flat pixels are matched with the same search as the rest: here, the lookup table's cell for 0..=3 is matched
+    // as 1.5, a tie the first color wins, though an exact search would match 2 to RGB(3, 0, 0).
+    use super::Search;
+    use crate::quantize::Quantizer;
+    let palette = [RGB(0, 0, 0), RGB(3, 0, 0)];
+    let img: Img<RGB<f64>> = Img::new(vec![RGB(2., 0., 0.); 4], 2).unwrap();
+    let mut lut = super::quantize(&palette).search(Search::Lut);
+    assert_eq!(lut.nearest(&RGB(2., 0., 0.), (0, 0)), RGB(0., 0., 0.));
+    let got = crate::ditherer::FLOYD_STEINBERG.dither(img.clone(), lut.flat(&img, 0.));
+    assert!(got.iter().all(|p| *p == RGB(0., 0., 0.)));
+}
+
+#[test]
+fn quantize_with_every_distance() {
+    use super::crayon;
+    use crate::color::distance::ColorDistance;
+    use crate::prelude::*;
+    use crate::quantize::Quantizer;
+    use clap::ValueEnum;
+    for &distance in ColorDistance::value_variants() {
+        let mut quantize = super::quantize_with(crayon::ALL, distance);
+        // every palette color is its own nearest neighbor...
+        for &color in crayon::ALL {
+            let color: RGB<f64> = RGB::from(color);
+            assert_eq!(quantize.nearest(&color, (0, 0)), color, "{:?}", distance);
+        }
+        // ...and everything else, including pixels carrying error outside 0..255, lands on one.
+        for p in [
+            RGB::<f64>(-40., 300., 12.),
+            RGB(128., 64., 200.),
+            RGB(0.5, 0.5, 0.5),
+        ] {
+            let nearest = quantize.nearest(&p, (0, 0));
+            assert!(
+                crayon::ALL.iter().any(|&c| RGB::<f64>::from(c) == nearest),
+                "{:?}",
+                distance
+            );
+        }
+    }
+}
+
+#[test]
+fn search_matches_linear() {
+    use super::Search;
+    use crate::color::distance::ColorDistance;
+    use crate::prelude::*;
+    use crate::quantize::Quantizer;
+    use clap::ValueEnum;
+    // a scattered 512-color palette, with a duplicate to check ties go to the first color.
+    let mut seed: u32 = 12345;
+    let mut next = move || {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        (seed >> 16) as u8
+    };
+    let mut palette: Vec<RGB<u8>> = (0..512).map(|_| RGB(next(), next(), next())).collect();
+    palette.push(palette[7]);
+    let pixels: Vec<RGB<f64>> = (0..2000)
+        .map(|_| RGB(next(), next(), next()).convert_with(|c| f64::from(c) * 1.2 - 25.))
+        .collect();
+    for &distance in ColorDistance::value_variants() {
+        let mut linear = super::quantize_with(&palette, distance).search(Search::Linear);
+        let mut tree = super::quantize_with(&palette, distance).search(Search::KdTree);
+        for p in &pixels {
+            let want = linear.nearest(p, (0, 0));
+            assert_eq!(tree.nearest(p, (0, 0)), want, "{:?} {:?}", distance, p);
+        }
+        // a tree this small is a single leaf, scanned one color at a time; the linear scan ranks them in batches,
+        // padded out for odd lengths.
+        for len in 1..=8 {
+            let mut linear = super::quantize_with(&palette[..len], distance).search(Search::Linear);
+            let mut tree = super::quantize_with(&palette[..len], distance).search(Search::KdTree);
+            for p in &pixels {
+                let want = tree.nearest(p, (0, 0));
+                assert_eq!(linear.nearest(p, (0, 0)), want, "{:?} {:?}", distance, p);
+            }
+        }
+    }
+    // the lookup table is approximate, but still always picks a palette color.
+    let mut lut = super::quantize(&palette).search(Search::Lut);
+    for p in &pixels {
+        let approx = lut.nearest(p, (0, 0));
+        assert!(palette.iter().any(|&c| RGB::<f64>::from(c) == approx));
+    }
+}
+
+#[test]
+fn extract_every_method() {
+    use super::extract::{self, Method};
+    use crate::prelude::*;
+    use clap::ValueEnum;
+    let four = [cga::BLACK, cga::RED, cga::YELLOW, cga::LIGHT_BLUE];
+    let img = Img::new((0..64).map(|i| four[i % 4]), 8).unwrap();
+    let scattered = Img::new(
+        (0..=255u8).map(|i| RGB(i, i.wrapping_mul(7), i.wrapping_mul(13))),
+        16,
+    )
+    .unwrap();
+    for &method in Method::value_variants() {
+        // an image with few enough colors gets exactly them...
+        let got: HashSet<_> = extract::extract(&img, 16, method).into_iter().collect();
+        assert_eq!(got, four.into_iter().collect(), "{:?}", method);
+        // ...and otherwise, the palette has as many colors as asked for.
+        for colors in [2, 3, 16, 100] {
+            let got = extract::extract(&scattered, colors, method);
+            assert_eq!(got.len(), colors, "{:?}", method);
+        }
+    }
+}
+
+#[test]
+fn subset_finds_the_colors_used() {
+    use super::subset;
+    use crate::color::distance::ColorDistance;
+    use crate::prelude::*;
+    // a 64-color master palette: 2 bits per channel.
+    let master: Vec<RGB<u8>> = (0..64u8)
+        .map(|i| RGB((i >> 4) * 85, (i >> 2 & 3) * 85, (i & 3) * 85))
+        .collect();
+    // 4 of 16 is searched exhaustively; 12 of 64 locally.
+    for (palette, colors) in [(cga::ALL, 4), (&master[..], 12)] {
+        let used: Vec<RGB<u8>> = palette.iter().copied().step_by(3).take(colors).collect();
+        let img = Img::new((0..256).map(|i| used[i * 7 % colors]), 16).unwrap();
+        for distance in [ColorDistance::Manhattan, ColorDistance::Oklab] {
+            assert_eq!(subset::select(&img, palette, colors, distance), used);
+        }
+    }
+}
+
+#[test]
+fn read_every_format() {
+    use super::format::{self, Format};
+    let want = vec![RGB(255, 0, 0), RGB(0, 0, 0), RGB(255, 255, 255)];
+    let text: [(Format, &str); 5] = [
+        (
+            Format::Gpl,
+            "GIMP Palette\nName: test\nColumns: 3\n# comment\n255   0   0 red\n  0   0   0\tblack\n255 255 255 white\n",
+        ),
+        (
+            Format::JascPal,
+            "JASC-PAL\r\n0100\r\n3\r\n255 0 0\r\n0 0 0\r\n255 255 255\r\n",
+        ),
+        (Format::PaintNet, "; paint.net\n;\nFFFF0000\nFF000000\nFFFFFFFF\n"),
+        (Format::Hex, "ff0000\n000000\nffffff\n"),
+        (Format::Plt, "// red\n0xff0000\n0x000000\n0xffffff\n"),
+    ];
+    for (want_format, contents) in text {
+        let got_format = Format::detect(None, contents.as_bytes());
+        // hex lists are indistinguishable from .plt files without an extension.
+        if want_format != Format::Hex {
+            assert_eq!(got_format, want_format);
+        }
+        assert_eq!(
+            format::read(contents.as_bytes(), want_format).unwrap(),
+            want
+        );
+    }
+    // two colors are enough in every format, .plt included; one isn't.
+    assert_eq!(
+        format::read(b"0x000000\n0xffffff\n", Format::Plt).unwrap(),
+        vec![RGB(0, 0, 0), RGB(255, 255, 255)]
+    );
+    assert!(format::read(b"0x000000\n", Format::Plt).is_err());
+
+    // a .txt is read as Paint.NET if it can be, and by its contents if not, like this .plt file.
+    for (name, contents) in [
+        ("temp_paint_net.txt", "FFFF0000\nFF000000\nFFFFFFFF\n"),
+        (
+            "temp_plt.txt",
+            "// red\n0xff0000\n// black\n0x000000\n0xffffff\n",
+        ),
+    ] {
+        let mut path = std::env::current_dir().unwrap();
+        path.push(name);
+        std::fs::write(&path, contents).unwrap();
+        let got = format::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(got.unwrap(), want, "{}", name);
+    }
+    let mut path = std::env::current_dir().unwrap();
+    path.push("temp_bad.txt");
+    std::fs::write(&path, "FFFF0000\nnot a color\n").unwrap();
+    let got = format::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(
+        got,
+        Err(Error::BadPalette {
+            format: Format::PaintNet,
+            ..
+        })
+    ));
+
+    // Adobe Color Swatches: version 1 RGB, CMYK (0 is full coverage), and grayscale (0 is white) colors,
+    // then a version 2 section with names, which is skipped.
+    let mut aco = vec![0, 1, 0, 3];
+    for color in [
+        [0, 0xffff, 0, 0, 0],
+        [2, 0xffff, 0xffff, 0xffff, 0],
+        [8, 0, 0, 0, 0],
+    ] {
+        aco.extend(color.iter().flat_map(|c: &u16| c.to_be_bytes()));
+    }
+    aco.extend([0, 2, 0, 0]);
+    assert_eq!(Format::detect(None, &aco), Format::Aco);
+    assert_eq!(format::read(&aco, Format::Aco).unwrap(), want);
+
+    // Adobe Swatch Exchange: a group of RGB, CMYK and gray colors.
+    let mut ase = b"ASEF\x00\x01\x00\x00\x00\x00\x00\x05".to_vec();
+    let mut block = |kind: u16, data: Vec<u8>| {
+        ase.extend(kind.to_be_bytes());
+        ase.extend((data.len() as u32).to_be_bytes());
+        ase.extend(data);
+    };
+    let name: Vec<u8> = [0u8, 2, 0, b'x', 0, 0].to_vec();
+    block(0xc001, name.clone());
+    for (model, values) in [
+        (b"RGB ", vec![1f32, 0., 0.]),
+        (b"CMYK", vec![0., 0., 0., 1.]),
+        (b"Gray", vec![1.]),
+    ] {
+        let mut data = name.clone();
+        data.extend(model);
+        data.extend(values.iter().flat_map(|v| v.to_be_bytes()));
+        data.extend([0, 2]);
+        block(1, data);
+    }
+    block(0xc002, Vec::new());
+    assert_eq!(Format::detect(None, &ase), Format::Ase);
+    assert_eq!(format::read(&ase, Format::Ase).unwrap(), want);
+
+    assert_eq!(
+        format::read(b"GIMP Palette\n255 0\n", Format::Gpl),
+        Err(Error::BadPalette {
+            format: Format::Gpl,
+            reason: "expected a decimal \"R G B\" color, got \"255 0\"".to_string()
+        })
+    );
+    assert_eq!(
+        format::read(&ase[..20], Format::Ase),
+        Err(Error::BadPalette {
+            format: Format::Ase,
+            reason: "unexpected end of file".to_string()
+        })
+    );
+}
+
+#[test]
+fn write_then_read_every_format() {
+    use super::format::{self, Format};
+    let two = &cga::ALL[..2];
+    for format in [
+        Format::Plt,
+        Format::Gpl,
+        Format::JascPal,
+        Format::PaintNet,
+        Format::Aco,
+        Format::Ase,
+        Format::Hex,
+    ] {
+        for palette in [cga::ALL, two] {
+            let written = format::write(palette, "CGA", format);
+            assert_eq!(
+                format::read(&written, format).unwrap(),
+                palette,
+                "{}",
+                format
+            );
+        }
+    }
+}
+
+#[test]
+fn built_in_palettes() {
+    for (name, len) in [
+        ("ega", 64),
+        ("gameboy", 4),
+        ("pico-8", 16),
+        ("nes", 55),
+        ("c64", 16),
+        ("zx_spectrum", 15),
+        ("apple2", 15),
+        ("msx", 15),
+        ("amstrad_cpc", 27),
+        ("mac16", 16),
+        ("web_safe", 216),
+        ("win16", 16),
+        ("teletext", 8),
+        ("epaper3", 3),
+        ("epaper3_yellow", 3),
+        ("epaper7", 7),
+    ] {
+        let Ok(Mode::Palette { palette, .. }) = name.parse::<Mode>() else {
+            panic!("{} should be a built-in palette", name);
+        };
+        let distinct: HashSet<_> = palette.iter().collect();
+        assert_eq!((palette.len(), distinct.len()), (len, len), "{}", name);
+    }
+    // the VGA's default palette starts with the CGA's, and ends with 8 repeated blacks.
+    let Ok(Mode::Palette { palette: vga, .. }) = "VGA".parse::<Mode>() else {
+        panic!("VGA should be a built-in palette");
+    };
+    assert_eq!(vga.len(), 256);
+    assert_eq!(&vga[..16], cga::ALL);
+    assert_eq!(
+        vga[32..36],
+        [
+            RGB(0, 0, 0xFF),
+            RGB(0x41, 0, 0xFF),
+            RGB(0x7D, 0, 0xFF),
+            RGB(0xBE, 0, 0xFF)
+        ]
+    );
+    assert!(vga[248..].iter().all(|&c| c == cga::BLACK));
+}
@@ -0,0 +1,63 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// the 256 colors of the VGA's default palette in mode 13h: the 16 [CGA][super::cga::ALL] colors, 16 grays,
+/// 216 colors in 9 rings of 24 hues at three intensities and three saturations, then 8 blacks.
+/// the DAC's 6-bit levels are scaled to 8 bits by repeating their top bits.
+pub const ALL: &Palette = &{
+    let mut all = [RGB(0, 0, 0); 256];
+    let mut i = 0;
+    while i < 16 {
+        all[i] = super::cga::ALL[i];
+        all[16 + i] = RGB(dac(GRAYS[i]), dac(GRAYS[i]), dac(GRAYS[i]));
+        i += 1;
+    }
+    let mut ring = 0;
+    while ring < 9 {
+        let levels = RINGS[ring];
+        let mut hue = 0;
+        while hue < 24 {
+            all[32 + ring * 24 + hue] = RGB(
+                ring_level(levels, hue, 4),
+                ring_level(levels, hue, 12),
+                ring_level(levels, hue, 20),
+            );
+            hue += 1;
+        }
+        ring += 1;
+    }
+    all
+};
+
+/// the level of a channel at `hue` in a ring. each channel holds at the top level for 9 hues from `start`, steps
+/// down for 3, holds at the bottom for 9 and steps back up for 3: blue starts at hue 20, red at 4 and green at 12.
+const fn ring_level(levels: [u8; 5], hue: usize, start: usize) -> u8 {
+    let step = (hue + 24 - start) % 24;
+    dac(levels[match step {
+        0..=8 => 4,
+        9..=11 => 12 - step,
+        12..=20 => 0,
+        _ => step - 20,
+    }])
+}
+
+/// scale a 6-bit DAC level to 8 bits.
+const fn dac(level: u8) -> u8 {
+    level << 2 | level >> 4
+}
+
+const GRAYS: [u8; 16] = [0, 5, 8, 11, 14, 17, 20, 24, 28, 32, 36, 40, 45, 50, 56, 63];
+
+/// the 5 levels each ring steps its channels through: high, medium and low intensity, each at high, medium and
+/// low saturation.
+const RINGS: [[u8; 5]; 9] = [
+    [0, 16, 31, 47, 63],
+    [31, 39, 47, 55, 63],
+    [45, 49, 54, 58, 63],
+    [0, 7, 14, 21, 28],
+    [14, 17, 21, 24, 28],
+    [20, 22, 24, 26, 28],
+    [0, 4, 8, 12, 16],
+    [8, 10, 12, 14, 16],
+    [11, 12, 13, 15, 16],
+];
@@ -0,0 +1,17 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// the 216 web-safe colors: every combination of 0x00, 0x33, 0x66, 0x99, 0xCC and 0xFF, with red changing slowest.
+pub const ALL: &Palette = &{
+    let mut all = [RGB(0, 0, 0); 216];
+    let mut i = 0;
+    while i < 216 {
+        all[i] = RGB(
+            (i / 36 * 0x33) as u8,
+            (i / 6 % 6 * 0x33) as u8,
+            (i % 6 * 0x33) as u8,
+        );
+        i += 1;
+    }
+    all
+};
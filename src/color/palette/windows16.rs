@@ -0,0 +1,25 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// the 16 colors of the Windows default palette, in its color number order.
+pub const ALL: &Palette = &[
+    BLACK, MAROON, GREEN, OLIVE, NAVY, PURPLE, TEAL, SILVER, GRAY, RED, LIME, YELLOW, BLUE,
+    FUCHSIA, AQUA, WHITE,
+];
+
+pub const BLACK: RGB<u8> = RGB(0x00, 0x00, 0x00);
+pub const MAROON: RGB<u8> = RGB(0x80, 0x00, 0x00);
+pub const GREEN: RGB<u8> = RGB(0x00, 0x80, 0x00);
+pub const OLIVE: RGB<u8> = RGB(0x80, 0x80, 0x00);
+pub const NAVY: RGB<u8> = RGB(0x00, 0x00, 0x80);
+pub const PURPLE: RGB<u8> = RGB(0x80, 0x00, 0x80);
+pub const TEAL: RGB<u8> = RGB(0x00, 0x80, 0x80);
+pub const SILVER: RGB<u8> = RGB(0xC0, 0xC0, 0xC0);
+pub const GRAY: RGB<u8> = RGB(0x80, 0x80, 0x80);
+pub const RED: RGB<u8> = RGB(0xFF, 0x00, 0x00);
+pub const LIME: RGB<u8> = RGB(0x00, 0xFF, 0x00);
+pub const YELLOW: RGB<u8> = RGB(0xFF, 0xFF, 0x00);
+pub const BLUE: RGB<u8> = RGB(0x00, 0x00, 0xFF);
+pub const FUCHSIA: RGB<u8> = RGB(0xFF, 0x00, 0xFF);
+pub const AQUA: RGB<u8> = RGB(0x00, 0xFF, 0xFF);
+pub const WHITE: RGB<u8> = RGB(0xFF, 0xFF, 0xFF);
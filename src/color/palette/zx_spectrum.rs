@@ -0,0 +1,37 @@
+use crate::color::rgb::RGB;
+use crate::color::Palette;
+
+/// the 15 colors of the ZX Spectrum: 8 at normal brightness, then 7 bright ones. black looks the same either way.
+pub const ALL: &Palette = &[
+    BLACK,
+    BLUE,
+    RED,
+    MAGENTA,
+    GREEN,
+    CYAN,
+    YELLOW,
+    WHITE,
+    BRIGHT_BLUE,
+    BRIGHT_RED,
+    BRIGHT_MAGENTA,
+    BRIGHT_GREEN,
+    BRIGHT_CYAN,
+    BRIGHT_YELLOW,
+    BRIGHT_WHITE,
+];
+
+pub const BLACK: RGB<u8> = RGB(0x00, 0x00, 0x00);
+pub const BLUE: RGB<u8> = RGB(0x00, 0x00, 0xD7);
+pub const RED: RGB<u8> = RGB(0xD7, 0x00, 0x00);
+pub const MAGENTA: RGB<u8> = RGB(0xD7, 0x00, 0xD7);
+pub const GREEN: RGB<u8> = RGB(0x00, 0xD7, 0x00);
+pub const CYAN: RGB<u8> = RGB(0x00, 0xD7, 0xD7);
+pub const YELLOW: RGB<u8> = RGB(0xD7, 0xD7, 0x00);
+pub const WHITE: RGB<u8> = RGB(0xD7, 0xD7, 0xD7);
+pub const BRIGHT_BLUE: RGB<u8> = RGB(0x00, 0x00, 0xFF);
+pub const BRIGHT_RED: RGB<u8> = RGB(0xFF, 0x00, 0x00);
+pub const BRIGHT_MAGENTA: RGB<u8> = RGB(0xFF, 0x00, 0xFF);
+pub const BRIGHT_GREEN: RGB<u8> = RGB(0x00, 0xFF, 0x00);
+pub const BRIGHT_CYAN: RGB<u8> = RGB(0x00, 0xFF, 0xFF);
+pub const BRIGHT_YELLOW: RGB<u8> = RGB(0xFF, 0xFF, 0x00);
+pub const BRIGHT_WHITE: RGB<u8> = RGB(0xFF, 0xFF, 0xFF);
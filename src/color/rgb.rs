@@ -112,6 +112,121 @@ impl RGB<f64> {
         let RGB(r, g, b) = self;
         r * 0.2126 + g * 0.7152 + b * 0.0722
     }
+
+    /// the linear-light value of each channel, from 0 to 1. values outside 0..255, like pixels carrying
+    /// error, are extended symmetrically.
+    fn to_linear(&self) -> [f64; 3] {
+        let linear = |c: f64| {
+            let c = c / 255.;
+            let magnitude = if c.abs() <= 0.04045 {
+                c.abs() / 12.92
+            } else {
+                ((c.abs() + 0.055) / 1.055).powf(2.4)
+            };
+            magnitude.copysign(c)
+        };
+        let RGB(r, g, b) = self;
+        [linear(*r), linear(*g), linear(*b)]
+    }
+
+    /// the sRGB color of linear-light channels; the inverse of [RGB::to_linear].
+    fn from_linear([r, g, b]: [f64; 3]) -> Self {
+        let encode = |c: f64| {
+            let magnitude = if c.abs() <= 0.003_130_8 {
+                12.92 * c.abs()
+            } else {
+                1.055 * c.abs().powf(1. / 2.4) - 0.055
+            };
+            255. * magnitude.copysign(c)
+        };
+        RGB(encode(r), encode(g), encode(b))
+    }
+
+    /// the [CIELAB](https://en.wikipedia.org/wiki/CIELAB_color_space) coordinates `[L, a, b]` of an sRGB color,
+    /// under a D65 white point. `L` runs from 0 to 100.
+    /// ```
+    /// # use dither::prelude::*;
+    /// let [l, a, b] = RGB(255., 0., 0.).to_lab();
+    /// assert_eq!([l.round(), a.round(), b.round()], [53., 80., 67.]);
+    /// ```
+    pub fn to_lab(&self) -> [f64; 3] {
+        let [r, g, b] = self.to_linear();
+        let x = (0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b) / 0.950_47;
+        let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+        let z = (0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b) / 1.088_83;
+        let f = |t: f64| {
+            const DELTA: f64 = 6. / 29.;
+            if t > DELTA.powi(3) {
+                t.cbrt()
+            } else {
+                t / (3. * DELTA * DELTA) + 4. / 29.
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+        [116. * fy - 16., 500. * (fx - fy), 200. * (fy - fz)]
+    }
+
+    /// the sRGB color of CIELAB coordinates; the inverse of [RGB::to_lab].
+    /// ```
+    /// # use dither::prelude::*;
+    /// let RGB(r, g, b) = RGB::from_lab(RGB(10., 150., 200.).to_lab());
+    /// assert!((r - 10.).abs() < 1e-3 && (g - 150.).abs() < 1e-3 && (b - 200.).abs() < 1e-3);
+    /// ```
+    pub fn from_lab([l, a, b]: [f64; 3]) -> Self {
+        let fy = (l + 16.) / 116.;
+        let (fx, fz) = (fy + a / 500., fy - b / 200.);
+        let f_inv = |t: f64| {
+            const DELTA: f64 = 6. / 29.;
+            if t > DELTA {
+                t.powi(3)
+            } else {
+                3. * DELTA * DELTA * (t - 4. / 29.)
+            }
+        };
+        let (x, y, z) = (0.950_47 * f_inv(fx), f_inv(fy), 1.088_83 * f_inv(fz));
+        RGB::from_linear([
+            3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z,
+            -0.969_266 * x + 1.876_010_8 * y + 0.041_556 * z,
+            0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z,
+        ])
+    }
+
+    /// the [Oklab](https://bottosson.github.io/posts/oklab/) coordinates `[L, a, b]` of an sRGB color.
+    /// `L` runs from 0 to 1.
+    /// ```
+    /// # use dither::prelude::*;
+    /// let [l, a, b] = RGB(255., 255., 255.).to_oklab();
+    /// assert!((l - 1.).abs() < 1e-4 && a.abs() < 1e-4 && b.abs() < 1e-4);
+    /// ```
+    pub fn to_oklab(&self) -> [f64; 3] {
+        let [r, g, b] = self.to_linear();
+        let l = (0.412_221_470_8 * r + 0.536_332_536_3 * g + 0.051_445_992_9 * b).cbrt();
+        let m = (0.211_903_498_2 * r + 0.680_699_545_1 * g + 0.107_396_956_6 * b).cbrt();
+        let s = (0.088_302_461_9 * r + 0.281_718_837_6 * g + 0.629_978_700_5 * b).cbrt();
+        [
+            0.210_454_255_3 * l + 0.793_617_785 * m - 0.004_072_046_8 * s,
+            1.977_998_495_1 * l - 2.428_592_205 * m + 0.450_593_709_9 * s,
+            0.025_904_037_1 * l + 0.782_771_766_2 * m - 0.808_675_766 * s,
+        ]
+    }
+
+    /// the sRGB color of Oklab coordinates; the inverse of [RGB::to_oklab].
+    /// ```
+    /// # use dither::prelude::*;
+    /// let RGB(r, g, b) = RGB::from_oklab(RGB(10., 150., 200.).to_oklab());
+    /// assert!((r - 10.).abs() < 1e-3 && (g - 150.).abs() < 1e-3 && (b - 200.).abs() < 1e-3);
+    /// ```
+    pub fn from_oklab([l, a, b]: [f64; 3]) -> Self {
+        let l_ = l + 0.396_337_777_4 * a + 0.215_803_757_3 * b;
+        let m_ = l - 0.105_561_345_8 * a - 0.063_854_172_8 * b;
+        let s_ = l - 0.089_484_177_5 * a - 1.291_485_548 * b;
+        let (l, m, s) = (l_.powi(3), m_.powi(3), s_.powi(3));
+        RGB::from_linear([
+            4.076_741_662_1 * l - 3.307_711_591_3 * m + 0.230_969_929_2 * s,
+            -1.268_438_004_6 * l + 2.609_757_401_1 * m - 0.341_319_396_5 * s,
+            -0.004_196_086_3 * l - 0.703_418_614_7 * m + 1.707_614_701 * s,
+        ])
+    }
 }
 
 impl RGB<u8> {
@@ -121,6 +236,8 @@ impl RGB<u8> {
     /// # use dither::prelude::*;
     /// assert_eq!(unsafe{RGB::from_hex(0xff_aa_bb)}, RGB(0xff, 0xaa, 0xbb));
     /// ```
+    /// # Safety
+    /// this function is always memory-safe; it's marked `unsafe` because it silently truncates inputs larger than `0xFFFFFF`.
     pub const unsafe fn from_hex(hex: u32) -> Self {
         super::RGB((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
     }
@@ -143,7 +260,7 @@ impl RGB<u8> {
 
 impl std::fmt::LowerHex for RGB<u8> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{:06x}", self.clone().to_hex())
+        write!(f, "{:06x}", self.to_hex())
     }
 }
 
@@ -0,0 +1,33 @@
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// RGBA represents a quadruplet of channels (r, g, b, alpha).
+/// Alpha is dithered like any other channel.
+pub struct RGBA<N>(pub N, pub N, pub N, pub N);
+
+impl<P> RGBA<P> {
+    /// map a function across all channels of the RGBA.
+    /// ```
+    /// # use dither::color::RGBA;
+    /// assert_eq!(RGBA(2_u8, 5, 8, 255).convert_with(f64::from), RGBA(2., 5., 8., 255.));
+    /// ```
+    pub fn convert_with<Q>(self, mut convert: impl FnMut(P) -> Q) -> RGBA<Q> {
+        let RGBA(r, g, b, a) = self;
+        RGBA(convert(r), convert(g), convert(b), convert(a))
+    }
+
+    /// split into the color and the alpha channel.
+    /// ```
+    /// # use dither::prelude::*;
+    /// # use dither::color::RGBA;
+    /// assert_eq!(RGBA(1, 2, 3, 4).split_alpha(), (RGB(1, 2, 3), 4));
+    /// ```
+    pub fn split_alpha(self) -> (super::RGB<P>, P) {
+        let RGBA(r, g, b, a) = self;
+        (super::RGB(r, g, b), a)
+    }
+}
+
+impl<N> From<(super::RGB<N>, N)> for RGBA<N> {
+    fn from((super::RGB(r, g, b), a): (super::RGB<N>, N)) -> Self {
+        RGBA(r, g, b, a)
+    }
+}
@@ -0,0 +1,112 @@
+//! Working color spaces for error diffusion. Corresponds to [Opt][crate::Opt] `--space`.
+//!
+//! Error diffusion keeps each region's average color, as measured in the space the error is spread in. Equal steps
+//! in sRGB aren't equal steps in what the eye sees, so sRGB averages can shift hues; i.e, greens toward cyan with
+//! the CGA palette. Converting the image to CIELAB or Oklab up front, and spreading the error there, keeps the
+//! average in a perceptual space instead. Colors far outside the palette's gamut can pile up error in `a` and `b`,
+//! though, so which space looks best depends on the image and palette.
+//! ```
+//! # use dither::prelude::*;
+//! # use dither::color::{palette::{self, cga}, space::{InSpace, Space}};
+//! let img: Img<RGB<f64>> = Img::new(vec![RGB(60., 140., 60.); 64], 8).unwrap();
+//! let quantize = InSpace::new(Space::Oklab, palette::quantize_with(cga::ALL, Space::Oklab.distance()));
+//! let got = Ditherer::default().dither(Space::Oklab.convert(img), quantize);
+//! let got = Space::Oklab.convert_back(got).convert_with(|rgb| rgb.convert_with(dither::clamp_f64_to_u8));
+//! assert!(got.iter().all(|p| cga::ALL.contains(p)));
+//! ```
+use super::distance::ColorDistance;
+use super::RGB;
+use crate::quantize::{Float, Quantizer};
+use crate::Img;
+
+/// A color space to spread error in. Pixels in a working space are `[N; 3]` arrays of its coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum Space {
+    /// sRGB, the space images are stored in: the red, green and blue channels, from 0 to 255.
+    #[default]
+    #[value(name = "srgb")]
+    Srgb,
+    /// [CIELAB][RGB::to_lab].
+    #[value(name = "lab")]
+    Lab,
+    /// [Oklab][RGB::to_oklab].
+    #[value(name = "oklab")]
+    Oklab,
+}
+
+impl Space {
+    /// the coordinates of an sRGB color in this space.
+    pub fn from_rgb(self, rgb: &RGB<f64>) -> [f64; 3] {
+        match self {
+            Space::Srgb => {
+                let RGB(r, g, b) = rgb;
+                [*r, *g, *b]
+            }
+            Space::Lab => rgb.to_lab(),
+            Space::Oklab => rgb.to_oklab(),
+        }
+    }
+
+    /// the sRGB color at coordinates in this space.
+    pub fn to_rgb(self, p: [f64; 3]) -> RGB<f64> {
+        match self {
+            Space::Srgb => RGB::from(p),
+            Space::Lab => RGB::from_lab(p),
+            Space::Oklab => RGB::from_oklab(p),
+        }
+    }
+
+    /// the [ColorDistance] that measures straight lines in this space; the natural one to match colors with.
+    pub fn distance(self) -> ColorDistance {
+        match self {
+            Space::Srgb => ColorDistance::Manhattan,
+            Space::Lab => ColorDistance::DeltaE76,
+            Space::Oklab => ColorDistance::Oklab,
+        }
+    }
+
+    /// convert an sRGB image into this space.
+    pub fn convert<N: Float>(self, img: Img<RGB<N>>) -> Img<[N; 3]> {
+        img.convert_with(|rgb| {
+            self.from_rgb(&rgb.convert_with(Into::into))
+                .map(N::from_f64)
+        })
+    }
+
+    /// convert an image in this space back to sRGB; the inverse of [Space::convert].
+    pub fn convert_back<N: Float>(self, img: Img<[N; 3]>) -> Img<RGB<N>> {
+        img.convert_with(|p| self.to_rgb(p.map(Into::into)).convert_with(N::from_f64))
+    }
+}
+
+/// A quantizer for pixels in a working [Space], wrapping an sRGB quantizer like [Nearest][super::palette::Nearest].
+/// it quantizes each pixel to the sRGB quantizer's choice, and spreads the error in the working space;
+/// or none at all where the sRGB quantizer [is solid][Quantizer::is_solid], like [Flat][super::palette::Flat]'s flat regions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InSpace<Q> {
+    space: Space,
+    quantize: Q,
+}
+
+impl<Q> InSpace<Q> {
+    pub fn new(space: Space, quantize: Q) -> Self {
+        InSpace { space, quantize }
+    }
+}
+
+impl<N: Float, Q: Quantizer<RGB<f64>>> Quantizer<[N; 3]> for InSpace<Q> {
+    fn nearest(&mut self, p: &[N; 3], at: (usize, usize)) -> [N; 3] {
+        let rgb = self.space.to_rgb(p.map(Into::into));
+        let nearest = self.quantize.nearest(&rgb, at);
+        self.space.from_rgb(&nearest).map(N::from_f64)
+    }
+    fn error(&mut self, p: [N; 3], nearest: &[N; 3], at: (usize, usize)) -> [N; 3] {
+        if self.quantize.is_solid(at) {
+            return [N::from_f64(0.); 3];
+        }
+        std::array::from_fn(|i| N::from_f64(p[i].into() - nearest[i].into()))
+    }
+    fn is_solid(&self, at: (usize, usize)) -> bool {
+        self.quantize.is_solid(at)
+    }
+}
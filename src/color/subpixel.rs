@@ -0,0 +1,142 @@
+//! Sub-pixel (LCD stripe) dithering: treat the red, green and blue sub-pixels of each pixel as separate
+//! horizontal samples, each taking its channel's value a third of a pixel left of, on, or right of the pixel's
+//! center, then dither each channel's samples on their own. Corresponds to [Opt][crate::Opt] `--subpixel`.
+//!
+//! Neighboring sub-pixels belong to different channels, so the error of a sub-pixel mustn't spread to the next
+//! one over, as it would dithering the samples as one single-channel image three times as wide:
+//! the next sample of its own channel is three over. [split_by_channel] groups the samples by channel, so
+//! dithering them per channel does just that.
+//! ```
+//! # use dither::prelude::*;
+//! # use dither::color::subpixel::{self, Stripe};
+//! # use dither::quantize::{BitDepth, PerChannel};
+//! let img: Img<RGB<f64>> = Img::new((0..16).map(|x| RGB::from([x * 16; 3])), 8).unwrap();
+//! let quantize = PerChannel(BitDepth::new(1).unwrap());
+//!
+//! let samples = subpixel::split_by_channel(&img, Stripe::RGB);
+//! assert_eq!(samples.size(), img.size());
+//! let got = Ditherer::default().dither(samples, quantize);
+//! assert!(got.iter().all(|p| p.channels().all(|c| c == 0. || c == 255.)));
+//! ```
+use super::RGB;
+use crate::quantize::Float;
+use crate::{Error, Img, Result};
+
+/// The order of the sub-pixels in each pixel of the panel, left to right.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum Stripe {
+    #[default]
+    #[value(name = "rgb")]
+    RGB,
+    #[value(name = "bgr")]
+    BGR,
+}
+
+impl Stripe {
+    /// the channel (0 for red, 1 for green, 2 for blue) of the `i`th sub-pixel from the left.
+    fn channel(self, i: usize) -> usize {
+        match self {
+            Stripe::RGB => i,
+            Stripe::BGR => 2 - i,
+        }
+    }
+}
+
+/// split an image into its sub-pixels, as a single-channel image three times as wide.
+/// each sub-pixel sits a third of a pixel left of, on, or right of its pixel's center,
+/// and takes its channel's value there, interpolated between the neighboring pixels.
+/// neighboring samples belong to different channels, so dither [split_by_channel] instead.
+/// fails with [Error::SubpixelTooWide] if three times the width doesn't fit in a `u32`.
+/// ```
+/// # use dither::prelude::*;
+/// # use dither::color::subpixel::{self, Stripe};
+/// let img = Img::new(vec![RGB(0., 0., 0.), RGB(30., 60., 90.)], 2).unwrap();
+/// assert_eq!(subpixel::split(&img, Stripe::RGB).unwrap().into_vec(), vec![0., 0., 30., 20., 60., 90.]);
+/// assert_eq!(subpixel::split(&img, Stripe::BGR).unwrap().into_vec(), vec![0., 0., 10., 60., 60., 30.]);
+///
+/// let too_wide: Img<RGB<f64>> = Img::new(vec![], u32::MAX / 3 + 1).unwrap();
+/// assert!(matches!(subpixel::split(&too_wide, Stripe::RGB), Err(Error::SubpixelTooWide(_))));
+/// ```
+pub fn split<N: Float>(img: &Img<RGB<N>>, stripe: Stripe) -> Result<Img<N>> {
+    let (width, height) = img.size();
+    let samples_width = width.checked_mul(3).ok_or(Error::SubpixelTooWide(width))?;
+    let mut buf = Vec::with_capacity(img.len() * 3);
+    for y in 0..height {
+        for x in 0..width {
+            buf.extend((0..3).map(|i| N::from_f64(sample(img, stripe, (x, y), i))));
+        }
+    }
+    Ok(Img::from_vec(buf, samples_width).unwrap())
+}
+
+/// the sub-pixel samples of [split], grouped by channel: each pixel holds the samples of its own red, green and
+/// blue sub-pixels. dithering it per channel, as with [PerChannel][crate::quantize::PerChannel], spreads each
+/// sample's error only to samples of its own channel; then it's the output as is.
+/// ```
+/// # use dither::prelude::*;
+/// # use dither::color::subpixel::{self, Stripe};
+/// let img = Img::new(vec![RGB(0., 0., 0.), RGB(30., 60., 90.)], 2).unwrap();
+/// assert_eq!(
+///     subpixel::split_by_channel(&img, Stripe::RGB).into_vec(),
+///     vec![RGB(0., 0., 30.), RGB(20., 60., 90.)]
+/// );
+/// assert_eq!(
+///     subpixel::split_by_channel(&img, Stripe::BGR).into_vec(),
+///     vec![RGB(10., 0., 0.), RGB(30., 60., 60.)]
+/// );
+/// ```
+pub fn split_by_channel<N: Float>(img: &Img<RGB<N>>, stripe: Stripe) -> Img<RGB<N>> {
+    let width = img.width();
+    let pixels = (0..img.len() as u32).map(|i| {
+        let (x, y) = (i % width, i / width);
+        let mut rgb = [0.; 3];
+        for i in 0..3 {
+            rgb[stripe.channel(i)] = sample(img, stripe, (x, y), i);
+        }
+        RGB::<f64>::from(rgb).convert_with(N::from_f64)
+    });
+    Img::new(pixels, width).unwrap()
+}
+
+/// the `i`th sub-pixel from the left of the pixel at `(x, y)`: its channel's value a third of a pixel left of,
+/// on, or right of the pixel's center, interpolated between the pixel and its neighbor on that side.
+fn sample<N: Float>(img: &Img<RGB<N>>, stripe: Stripe, (x, y): (u32, u32), i: usize) -> f64 {
+    let c = stripe.channel(i);
+    let channel = |x: u32| -> f64 {
+        let RGB(r, g, b) = img[(x, y)].clone();
+        [r, g, b][c].into()
+    };
+    let neighbor = match i {
+        0 => x.checked_sub(1),
+        2 => Some(x + 1).filter(|&nx| nx < img.width()),
+        _ => None,
+    };
+    match neighbor {
+        Some(nx) => (2. * channel(x) + channel(nx)) / 3.,
+        None => channel(x),
+    }
+}
+
+/// recombine a single-channel image of sub-pixels, three to a pixel, into an [RGB] image; the inverse of [split].
+/// ```
+/// # use dither::prelude::*;
+/// # use dither::color::subpixel::{self, Stripe};
+/// let samples = Img::new(vec![1, 2, 3, 4, 5, 6], 6).unwrap();
+/// assert_eq!(subpixel::merge(samples.clone(), Stripe::RGB).into_vec(), vec![RGB(1, 2, 3), RGB(4, 5, 6)]);
+/// assert_eq!(subpixel::merge(samples, Stripe::BGR).into_vec(), vec![RGB(3, 2, 1), RGB(6, 5, 4)]);
+/// ```
+/// # Panics
+/// if the width isn't a multiple of 3.
+pub fn merge<N>(img: Img<N>, stripe: Stripe) -> Img<RGB<N>> {
+    let width = img.width();
+    assert!(width.is_multiple_of(3), "width must be a multiple of 3");
+    let mut samples = img.into_iter();
+    let mut pixels = Vec::with_capacity(samples.len() / 3);
+    while let (Some(a), Some(b), Some(c)) = (samples.next(), samples.next(), samples.next()) {
+        pixels.push(match stripe {
+            Stripe::RGB => RGB(a, b, c),
+            Stripe::BGR => RGB(c, b, a),
+        });
+    }
+    Img::from_vec(pixels, width / 3).unwrap()
+}
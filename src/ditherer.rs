@@ -1,13 +1,27 @@
 //! Logic for dithering a loaded, preprocessed [Img][crate::img::Img].
 //! See [tanner helland's excellent writeup on dithering algorithms](http://www.tannerhelland.com/4660/dithering-eleven-algorithms-source-code/) for details.
 use super::Img;
-use std::ops::{Add, Div, Mul};
+use crate::pixel::Pixel;
+use crate::quantize::Quantizer;
+
+pub mod audio;
+pub mod fixed;
+pub mod optimize;
+mod parallel;
+mod progress;
+pub mod registry;
+pub mod sequence;
+mod stream;
+pub mod temporal;
+pub use self::progress::Cancel;
+pub use self::stream::{RowDitherer, Rows};
 
 /// dither a 2d matrix.
-/// `P`  is the type of pixel; in practice, it is either [f64] or [`RGB<f64>`][RGB]
+/// `P`  is the type of pixel; any [Pixel], like a scalar [f64] or [f32], or an [`RGB`][crate::color::RGB] of one.
 pub trait Dither<P> {
-    fn dither(&self, img: Img<P>, quantize: impl FnMut(P) -> (P, P)) -> Img<P>;
+    fn dither(&self, img: Img<P>, quantize: impl Quantizer<P>) -> Img<P>;
 }
+
 /// A type of Dither. See the documentation for the constants (i.e, [ATKINSON]) for the dither matrices themselves.
 /// A ditherer carries error from quantiation to nearby pixels after dividing by `div` and multiplying by the given scalar in offset; "spreading" the error,
 /// eg, take floyd-steinberg dithering: `div=16`
@@ -20,6 +34,7 @@ pub trait Dither<P> {
 /// -   ` . ----  . ---    43.75`
 /// -   `43.75   31.25   6.25`
 ///
+/// Error that would be spread past the left or right edge of the image is dropped, as is any error aimed at pixels that have already been quantized.
 ///
 /// See [tanner helland's excellent writeup on dithering algorithms](http://www.tannerhelland.com/4660/dithering-eleven-algorithms-source-code/)
 /// for details.
@@ -43,48 +58,51 @@ impl<'a> Ditherer<'a> {
             name: None,
         }
     }
+
+    /// how many rows below the current one the ditherer spreads error to; i.e, the largest `dy` in its offsets.
+    /// ```
+    /// # use dither::ditherer::{FLOYD_STEINBERG, STUCKI};
+    /// assert_eq!(FLOYD_STEINBERG.reach(), 1);
+    /// assert_eq!(STUCKI.reach(), 2);
+    /// ```
+    pub fn reach(&self) -> usize {
+        self.forward_offsets()
+            .map(|(_, dy, _)| dy as usize)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// the offsets that spread error "forward" onto pixels that haven't been quantized yet; i.e, to the right or below.
+    fn forward_offsets(&self) -> impl Iterator<Item = (isize, isize, f64)> + 'a {
+        self.offsets
+            .iter()
+            .cloned()
+            .filter(|&(dx, dy, _)| dy > 0 || (dy == 0 && dx > 0))
+    }
 }
 
 impl<'a, P> Dither<P> for Ditherer<'a>
 where
-    P: Add<Output = P> + Clone + Default,           // vec addition
-    P: Mul<f64, Output = P> + Div<f64, Output = P>, // scalar multiplication
+    P: Pixel,
 {
     /// dither an image using the specified offsets and divisor.
-    /// `P` is the type of pixel; in practice, it is either [f64] or [RGB<f64]
-    fn dither(&self, mut img: Img<P>, mut quantize: impl FnMut(P) -> (P, P)) -> super::Img<P> {
-        let width = img.width() as isize;
-        let mut spillover = vec![P::default(); img.len()];
-        for (i, p) in img.iter_mut().enumerate() {
-            let (quantized, spill) = quantize(p.clone() + spillover[i].clone());
-            *p = quantized;
-
-            // add spillover matrices
-            for (dx, dy, mul) in self.offsets.iter().cloned() {
-                let j = i as isize + (dy * width) + dx;
-
-                if let Some(stored_spill) = spillover.get_mut(j as usize) {
-                    // this cast is OK, since if we go past the edges, we get zero
-                    *stored_spill = stored_spill.clone() + (spill.clone() * mul) / self.div;
-                }
-            }
+    /// `P` is the type of pixel; see [Pixel].
+    fn dither(&self, img: Img<P>, quantize: impl Quantizer<P>) -> super::Img<P> {
+        let width = img.width();
+        let mut stream = RowDitherer::new(self, width as usize, quantize);
+        let mut buf = img.into_vec();
+        for row in buf.chunks_mut(width as usize) {
+            stream.push_row(row);
         }
-        img
+        Img::from_vec(buf, width).unwrap()
     }
 }
 
 impl std::str::FromStr for Ditherer<'static> {
     type Err = ErrorUnknownDitherer;
+    /// a built-in kernel by name or alias, ignoring case; the same names [registry::Registry::default] accepts.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s.to_ascii_lowercase().as_ref() {
-            "floyd" | "steinberg" | "floydsteinberg" | "floyd steinberg" => FLOYD_STEINBERG,
-            "atkinson" => ATKINSON,
-            "stucki" => STUCKI,
-            "burkes" => BURKES,
-            "jarvis" | "judice" | "ninke" => JARVIS_JUDICE_NINKE,
-            "sierra" | "sierra3" => SIERRA_3,
-            _ => return Err(ErrorUnknownDitherer(s.to_string())),
-        })
+        registry::built_in(s).ok_or_else(|| ErrorUnknownDitherer(s.to_string()))
     }
 }
 
@@ -228,15 +246,7 @@ impl std::fmt::Display for ErrorUnknownDitherer {
 
 impl<'a> std::fmt::Display for Ditherer<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            if let Some(name) = self.name {
-                name
-            } else {
-                "custom ditherer"
-            }
-        )
+        f.write_str(self.name.unwrap_or("custom ditherer"))
     }
 }
 
@@ -0,0 +1,154 @@
+//! 1-D dithering for sample streams, i.e, reducing float or 24-bit audio to 16 or 8 bits.
+//!
+//! Samples are [f64]s in full scale (`-1.0..1.0`). A [Shaper] adds random [Noise] to each sample before
+//! quantizing it, to decorrelate the error from the signal, and feeds the error back through a noise-shaping
+//! filter, to push it up to frequencies where it's harder to hear. This is error diffusion in one dimension:
+//! a filter of `[1.0]` spreads all of a sample's error onto the next one.
+//! ```
+//! # use dither::ditherer::audio::{self, Noise, Shaper};
+//! let quantize = audio::quantize_bits(16).unwrap();
+//! let mut samples: Vec<f64> = (0..441).map(|n| 0.5 * f64::sin(n as f64 / 7.)).collect();
+//! let mut shaper = Shaper::new(audio::LIPSHITZ, Noise::Triangular(audio::lsb(16)));
+//! shaper.process(&mut samples, &quantize);
+//! let pcm: Vec<i16> = samples.iter().map(|&x| audio::to_pcm(x, 16) as i16).collect();
+//! assert!(pcm.iter().all(|&x| (-16_500..16_500).contains(&x)));
+//! ```
+use crate::quantize::Quantizer;
+use crate::{Error, Result};
+use std::borrow::Cow;
+
+/// no noise shaping; the error is white.
+pub const FLAT: &[f64] = &[];
+
+/// Lipshitz et al's 5-tap "minimally audible" noise-shaping filter, for 44.1kHz.
+pub const LIPSHITZ: &[f64] = &[2.033, -2.165, 1.959, -1.590, 0.6149];
+
+/// Wannamaker's 9-tap F-weighted noise-shaping filter, for 44.1kHz.
+pub const F_WEIGHTED: &[f64] = &[
+    2.412, -3.370, 3.937, -4.174, 3.353, -2.205, 1.281, -0.569, 0.0847,
+];
+
+/// the size of one least significant bit at a bit depth, in full scale.
+/// ```
+/// # use dither::ditherer::audio;
+/// assert_eq!(audio::lsb(8), 1. / 128.);
+/// ```
+pub fn lsb(bits: u8) -> f64 {
+    2. / f64::from(bits).exp2()
+}
+
+/// quantize a full-scale sample to `bits` bits, returning the pair `(nearest, error)` like
+/// [create_quantize_n_bits_func][crate::create_quantize_n_bits_func]. fails unless `bits` is between 1 and 24.
+/// ```
+/// # use dither::ditherer::audio;
+/// let eight_bit = audio::quantize_bits(8).unwrap();
+/// assert_eq!(eight_bit(0.3), (38. / 128., 0.3 - 38. / 128.));
+/// assert_eq!(eight_bit(1.0), (127. / 128., 1. / 128.));
+/// ```
+pub fn quantize_bits(bits: u8) -> Result<impl Fn(f64) -> (f64, f64) + Clone> {
+    if bits == 0 || bits > 24 {
+        return Err(Error::BadSampleDepth(bits));
+    }
+    let lsb = lsb(bits);
+    Ok(move |x: f64| {
+        let quot = f64::clamp(f64::round(x / lsb) * lsb, -1., 1. - lsb);
+        (quot, x - quot)
+    })
+}
+
+/// convert a quantized full-scale sample to a signed `bits`-bit integer.
+/// ```
+/// # use dither::ditherer::audio;
+/// assert_eq!(audio::to_pcm(-1., 16), -32768);
+/// assert_eq!(audio::to_pcm(0.5, 8), 64);
+/// ```
+pub fn to_pcm(x: f64, bits: u8) -> i32 {
+    f64::round(x / lsb(bits)) as i32
+}
+
+/// random noise added to each sample before quantizing it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Noise {
+    /// no noise.
+    None,
+    /// rectangular PDF noise, uniform over `±lsb / 2`.
+    Rectangular(f64),
+    /// triangular PDF noise, the sum of two rectangular ones: `±lsb`. Makes the error's mean and variance independent of the signal.
+    Triangular(f64),
+}
+
+/// A streaming noise shaper for one channel of audio. Keep one per channel, and feed each its samples in order;
+/// the filter's state carries over between calls to [Shaper::process].
+/// Leave some headroom: the error from clipping at full scale is fed back like any other.
+#[derive(Debug, Clone)]
+pub struct Shaper {
+    filter: Cow<'static, [f64]>,
+    noise: Noise,
+    /// the most recent errors, newest first.
+    history: Vec<f64>,
+    samples: usize,
+    rng: u64,
+}
+
+impl Shaper {
+    /// a shaper feeding the error back through `filter`, i.e, [LIPSHITZ] or [F_WEIGHTED].
+    /// `filter[k]` is the weight for the error `k + 1` samples back.
+    pub fn new(filter: impl Into<Cow<'static, [f64]>>, noise: Noise) -> Self {
+        let filter = filter.into();
+        Shaper {
+            history: vec![0.; filter.len()],
+            filter,
+            noise,
+            samples: 0,
+            rng: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    /// reseed the noise generator. the output is deterministic for a given seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = seed.max(1);
+        self
+    }
+
+    /// dither `samples` in place. `quantize` is told each sample's index in the stream as its position `(n, 0)`.
+    /// ```
+    /// # use dither::ditherer::audio::{self, Noise, Shaper};
+    /// // with no noise or shaping, it's just quantization.
+    /// let quantize = audio::quantize_bits(8).unwrap();
+    /// let mut samples = vec![0.3, -0.2, 0.9];
+    /// Shaper::new(audio::FLAT, Noise::None).process(&mut samples, &quantize);
+    /// assert_eq!(samples, [0.3, -0.2, 0.9].map(|x| quantize(x).0));
+    /// ```
+    pub fn process(&mut self, samples: &mut [f64], mut quantize: impl Quantizer<f64>) {
+        for x in samples {
+            let shaped = *x
+                - self
+                    .filter
+                    .iter()
+                    .zip(&self.history)
+                    .map(|(h, e)| h * e)
+                    .sum::<f64>();
+            let noise = match self.noise {
+                Noise::None => 0.,
+                Noise::Rectangular(lsb) => lsb * self.uniform(),
+                Noise::Triangular(lsb) => lsb * (self.uniform() + self.uniform()),
+            };
+            let quantized = quantize.nearest(&(shaped + noise), (self.samples, 0));
+            if !self.history.is_empty() {
+                self.history.rotate_right(1);
+                self.history[0] = quantized - shaped;
+            }
+            *x = quantized;
+            self.samples += 1;
+        }
+    }
+
+    /// uniform noise in `-0.5..0.5`.
+    fn uniform(&mut self) -> f64 {
+        // xorshift64: we don't need good randomness, just white noise.
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 11) as f64 / (1_u64 << 53) as f64 - 0.5
+    }
+}
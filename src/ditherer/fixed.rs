@@ -0,0 +1,263 @@
+//! Fixed-point integer error diffusion, for bit-exact output on every platform and for targets without an FPU.
+//!
+//! Pixels are `u8` channels, interleaved `channels` to a pixel. Carried error lives in `i32` accumulators as
+//! fixed-point numbers with [FRACTION_BITS] fractional bits, and is spread with integer kernel weights;
+//! no floating-point math happens while dithering. The last tap of the kernel gets whatever the other taps' rounding
+//! left over, so no error is lost. It's also faster than [Ditherer::dither] on floating point: about 1.5 times
+//! as fast for the built-in kernels on one x86_64 machine; run `cargo bench --bench fixed` to compare on yours.
+//! ```
+//! # use dither::ditherer::{fixed, FLOYD_STEINBERG};
+//! let kernel = FLOYD_STEINBERG.to_fixed().unwrap();
+//! let quantize = fixed::quantize_n_bits(1).unwrap();
+//! let mut gray: Vec<u8> = (0..64).map(|x| x * 4).collect();
+//! let mut scratch = vec![0; kernel.scratch_len(8, 1)];
+//! kernel.dither(&mut gray, 8, 1, &mut scratch, quantize);
+//! assert!(gray.iter().all(|&p| p == 0 || p == 255));
+//! ```
+use super::Ditherer;
+use crate::{Error, Result};
+
+/// the number of fractional bits in the fixed-point values passed to and returned from quantizers.
+pub const FRACTION_BITS: u32 = 8;
+/// `1.0` in fixed point.
+pub const ONE: i32 = 1 << FRACTION_BITS;
+
+/// An error-diffusion kernel with integer weights. See [Ditherer::to_fixed].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixedDitherer {
+    div: i32,
+    /// offsets represents a triplet (dx, dy, mul)
+    offsets: Vec<(isize, isize, i32)>,
+}
+
+impl<'a> Ditherer<'a> {
+    /// the equivalent integer kernel, or `None` if `div` or any of the weights aren't integers, or the weights
+    /// add up to more than `div`. all of the built-in kernels have integer weights that add up to at most `div`.
+    /// ```
+    /// # use dither::ditherer::{Ditherer, ATKINSON};
+    /// assert!(ATKINSON.to_fixed().is_some());
+    /// assert!(Ditherer::new(2., &[(1, 0, 0.5)]).to_fixed().is_none());
+    /// assert!(Ditherer::new(1., &[(1, 0, 2.)]).to_fixed().is_none());
+    /// ```
+    pub fn to_fixed(&self) -> Option<FixedDitherer> {
+        let to_int = |n: f64| {
+            if n.fract() == 0. && n.abs() <= f64::from(i16::MAX) {
+                Some(n as i32)
+            } else {
+                None
+            }
+        };
+        let div = to_int(self.div).filter(|&div| div != 0)?;
+        let offsets: Vec<_> = self
+            .forward_offsets()
+            .map(|(dx, dy, mul)| Some((dx, dy, to_int(mul)?)))
+            .collect::<Option<_>>()?;
+        if !FixedDitherer::damped(div, &offsets) {
+            return None;
+        }
+        Some(FixedDitherer { div, offsets })
+    }
+}
+
+impl FixedDitherer {
+    /// create an integer kernel directly. offsets that don't spread error forward (right or down) are ignored,
+    /// as with [Ditherer].
+    /// # Panics
+    /// if `div` is zero, or the magnitudes of the weights add up to more than `div`'s.
+    pub fn new(div: i32, offsets: &[(isize, isize, i32)]) -> Self {
+        assert_ne!(div, 0, "div must be nonzero");
+        let offsets: Vec<_> = offsets
+            .iter()
+            .cloned()
+            .filter(|&(dx, dy, _)| dy > 0 || (dy == 0 && dx > 0))
+            .collect();
+        assert!(
+            Self::damped(div, &offsets),
+            "the weights must add up to at most div"
+        );
+        FixedDitherer { div, offsets }
+    }
+
+    /// whether the magnitudes of the weights add up to at most `div`'s. a kernel that spreads more error than
+    /// there is amplifies it from pixel to pixel, until it overflows the `i32` accumulators; one that doesn't
+    /// never carries more error to a pixel than the pixels before it had.
+    fn damped(div: i32, offsets: &[(isize, isize, i32)]) -> bool {
+        let spread: u64 = offsets
+            .iter()
+            .map(|&(_, _, mul)| u64::from(mul.unsigned_abs()))
+            .sum();
+        spread <= u64::from(div.unsigned_abs())
+    }
+
+    /// the length of the scratch buffer [FixedDitherer::dither] needs for rows `width` pixels wide.
+    pub fn scratch_len(&self, width: usize, channels: usize) -> usize {
+        let reach = self.offsets.iter().map(|&(_, dy, _)| dy as usize).max();
+        (reach.unwrap_or(0) + 1) * (width + 2 * self.pad()) * channels
+    }
+
+    /// how far the kernel reaches to the left or right. each row of error is padded by this many pixels on both
+    /// sides, so error spread off the edge of the image lands in the padding and is dropped, without a check.
+    fn pad(&self) -> usize {
+        self.offsets
+            .iter()
+            .map(|&(dx, _, _)| dx.unsigned_abs())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// dither a buffer of tightly-packed rows in place, `width` pixels of `channels` interleaved `u8` channels per row.
+    /// each channel is dithered separately. `scratch` holds the error while dithering; its contents are overwritten.
+    ///
+    /// `quantize` takes a channel plus its carried error, in fixed point, and returns the quantized channel and
+    /// the remaining error, in fixed point. See [quantize_n_bits].
+    /// # Panics
+    /// if `buf` ends partway through a row, or `scratch` is shorter than [FixedDitherer::scratch_len].
+    pub fn dither(
+        &self,
+        buf: &mut [u8],
+        width: usize,
+        channels: usize,
+        scratch: &mut [i32],
+        mut quantize: impl FnMut(i32) -> (u8, i32),
+    ) {
+        let stride = width * channels;
+        if stride == 0 {
+            return;
+        }
+        assert!(
+            buf.len().is_multiple_of(stride),
+            "buffer ends partway through a row"
+        );
+        let errors = &mut scratch[..self.scratch_len(width, channels)];
+        errors.fill(0);
+        let padded_stride = (width + 2 * self.pad()) * channels;
+        let rows = errors.len() / padded_stride;
+        // where the image starts in each padded row of errors.
+        let left = self.pad() * channels;
+        let total: i64 = self.offsets.iter().map(|&(_, _, mul)| i64::from(mul)).sum();
+        let div = Divisor::new(self.div);
+        let Some((&(last_dx, last_dy, _), offsets)) = self.offsets.split_last() else {
+            // nothing to spread error to.
+            for p in buf.iter_mut() {
+                *p = quantize(i32::from(*p) << FRACTION_BITS).0;
+            }
+            return;
+        };
+        // where each tap spreads to in `errors`, from the first pixel of the row, and its weight.
+        // the last tap gets whatever's left over, so it's kept apart.
+        let at = |y: usize, dx: isize, dy: isize| {
+            let row_start = ((y + dy as usize) % rows) * padded_stride + left;
+            row_start.wrapping_add_signed(dx * channels as isize)
+        };
+        let mut taps: Vec<(usize, i64)> = Vec::with_capacity(offsets.len());
+
+        for (y, row) in buf.chunks_mut(stride).enumerate() {
+            let current = at(y, 0, 0);
+            taps.clear();
+            taps.extend(
+                offsets
+                    .iter()
+                    .map(|&(dx, dy, mul)| (at(y, dx, dy), i64::from(mul))),
+            );
+            let last = at(y, last_dx, last_dy);
+            for (k, p) in row.iter_mut().enumerate() {
+                let (quantized, spill) =
+                    quantize((i32::from(*p) << FRACTION_BITS) + errors[current + k]);
+                *p = quantized;
+
+                // each tap's share is truncated, so the last one gets whatever the others left over,
+                // and the taps add up to exactly `spill * total / div`.
+                let spill = i64::from(spill);
+                let mut remaining = div.divide(spill * total);
+                for &(start, mul) in &taps {
+                    let share = div.divide(spill * mul);
+                    remaining -= share;
+                    // no bigger than the spill, since the kernel is damped.
+                    errors[start + k] += share as i32;
+                }
+                errors[last + k] += remaining as i32;
+            }
+            let current_row = current - left;
+            errors[current_row..current_row + padded_stride].fill(0);
+        }
+    }
+}
+
+/// truncating division by a fixed divisor, as a multiplication and a shift, which is much cheaper than dividing:
+/// see Granlund and Montgomery, "Division by Invariant Integers using Multiplication".
+#[derive(Clone, Copy, Debug)]
+struct Divisor {
+    div: i64,
+    /// `2^shift / |div|`, rounded down, plus one.
+    magic: u64,
+    shift: u32,
+}
+
+impl Divisor {
+    /// numerators below `2^BITS` in magnitude are divided by the multiplication: the magic number is at most
+    /// `2^(BITS + 1)`, so their product with it fits in a `u64`. larger ones are divided with `/`.
+    const BITS: u32 = 31;
+
+    fn new(div: i32) -> Self {
+        let abs = u64::from(div.unsigned_abs());
+        // the smallest power of two at least `abs`, so the magic number's error is under `1 / abs`.
+        let shift = Self::BITS + abs.next_power_of_two().trailing_zeros();
+        Divisor {
+            div: i64::from(div),
+            magic: (1 << shift) / abs + 1,
+            shift,
+        }
+    }
+
+    /// `n / div`, rounded toward zero, like `/`.
+    fn divide(self, n: i64) -> i64 {
+        let abs = n.unsigned_abs();
+        if abs >= 1 << Self::BITS {
+            return n / self.div;
+        }
+        let q = ((abs * self.magic) >> self.shift) as i64;
+        if (n < 0) != (self.div < 0) {
+            -q
+        } else {
+            q
+        }
+    }
+}
+
+/// the fixed-point equivalent of [create_quantize_n_bits_func][crate::create_quantize_n_bits_func]:
+/// quantize a fixed-point channel to the nearest of `n + 1` evenly spaced levels between 0 and 255,
+/// returning the level as a `u8` and the fixed-point error.
+/// ```
+/// # use dither::ditherer::fixed::{self, ONE};
+/// let one_bit = fixed::quantize_n_bits(1).unwrap();
+/// assert_eq!(one_bit(100 * ONE), (0, 100 * ONE));
+/// assert_eq!(one_bit(250 * ONE), (255, -5 * ONE));
+/// ```
+pub fn quantize_n_bits(n: u8) -> Result<impl Fn(i32) -> (u8, i32)> {
+    if n == 0 || n > 7 {
+        return Err(Error::BadBitDepth(n));
+    }
+    let n = i32::from(n);
+    // a constant, so dividing by it is a multiplication.
+    const MAX: i32 = 255 * ONE;
+    // the levels, and a level past the last, so the one above the last level doesn't need a check.
+    let levels: Vec<i32> = (0..=n).chain([n]).map(|k| k * MAX / n).collect();
+    Ok(move |x: i32| {
+        let k = ((x.clamp(0, MAX) * n) / MAX) as usize;
+        let (floor, ceil) = (levels[k], levels[k + 1]);
+        let quot = if x - floor < ceil - x { floor } else { ceil };
+        (((quot + ONE / 2) >> FRACTION_BITS) as u8, x - quot)
+    })
+}
+
+/// the fixed-point equivalent of [RGB::to_chroma_corrected_black_and_white][crate::color::RGB::to_chroma_corrected_black_and_white],
+/// rounded to the nearest `u8`.
+/// ```
+/// # use dither::ditherer::fixed;
+/// # use dither::prelude::*;
+/// assert_eq!(fixed::luma(RGB(255, 255, 255)), 255);
+/// assert_eq!(fixed::luma(RGB(0, 255, 0)), 182);
+/// ```
+pub fn luma(crate::color::RGB(r, g, b): crate::color::RGB<u8>) -> u8 {
+    ((u32::from(r) * 2126 + u32::from(g) * 7152 + u32::from(b) * 722 + 5000) / 10000) as u8
+}
@@ -0,0 +1,187 @@
+//! Search for error-diffusion kernels that minimize a perceptual error metric over a set of training images.
+//! See the `dither-optimize` binary.
+use super::{Dither, Ditherer};
+use crate::Img;
+use std::num::NonZeroU32;
+
+/// An owned error-diffusion kernel; the result of [optimize].
+/// Its [Display][std::fmt::Display] is the equivalent [Ditherer::new] call.
+/// ```
+/// # use dither::ditherer::{optimize::Kernel, FLOYD_STEINBERG};
+/// let kernel = Kernel::from(&FLOYD_STEINBERG);
+/// assert_eq!(kernel.ditherer(), FLOYD_STEINBERG);
+/// assert_eq!(
+///     kernel.to_string(),
+///     "Ditherer::new(16.0, &[(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)])"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kernel {
+    pub div: f64,
+    /// offsets represents a triplet (dx, dy, mul)
+    pub offsets: Vec<(isize, isize, f64)>,
+}
+
+impl Kernel {
+    /// a [Ditherer] borrowing this kernel.
+    pub fn ditherer(&self) -> Ditherer<'_> {
+        Ditherer::new(self.div, &self.offsets)
+    }
+}
+
+impl<'a> From<&Ditherer<'a>> for Kernel {
+    fn from(ditherer: &Ditherer<'a>) -> Self {
+        Kernel {
+            div: ditherer.div,
+            offsets: ditherer.offsets.to_vec(),
+        }
+    }
+}
+
+impl std::fmt::Display for Kernel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Ditherer::new({:?}, &[", self.div)?;
+        for (i, (dx, dy, mul)) in self.offsets.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "({}, {}, {:?})", dx, dy, mul)?;
+        }
+        f.write_str("])")
+    }
+}
+
+/// the perceptual error between an original grayscale image and its dithered counterpart:
+/// the mean squared difference after blurring both with a 5x5 binomial filter, which roughly models how the eye
+/// averages neighboring pixels. `0.` for identical images.
+/// ```
+/// # use dither::prelude::*;
+/// # use dither::ditherer::optimize::perceptual_error;
+/// let a = Img::new(vec![0., 255., 0., 255.], 2).unwrap();
+/// assert_eq!(perceptual_error(&a, &a), 0.);
+/// ```
+pub fn perceptual_error(original: &Img<f64>, dithered: &Img<f64>) -> f64 {
+    assert_eq!(
+        original.size(),
+        dithered.size(),
+        "images must be the same size"
+    );
+    if original.is_empty() {
+        return 0.;
+    }
+    let (original, dithered) = (blur(original), blur(dithered));
+    let sum: f64 = original
+        .iter()
+        .zip(dithered.iter())
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum();
+    sum / original.len() as f64
+}
+
+/// a separable 5x5 binomial blur, clamping at the edges.
+fn blur(img: &Img<f64>) -> Img<f64> {
+    const WEIGHTS: [f64; 5] = [1., 4., 6., 4., 1.];
+    let (width, height) = img.size();
+    let sample = |img: &Img<f64>, (x, y): (i64, i64)| {
+        img[(
+            x.clamp(0, i64::from(width) - 1) as u32,
+            y.clamp(0, i64::from(height) - 1) as u32,
+        )]
+    };
+    let pass = |img: &Img<f64>, (dx, dy): (i64, i64)| {
+        let mut buf = Vec::with_capacity(img.len());
+        for y in 0..i64::from(height) {
+            for x in 0..i64::from(width) {
+                let sum: f64 = (-2..=2)
+                    .zip(WEIGHTS.iter())
+                    .map(|(i, w)| w * sample(img, (x + i * dx, y + i * dy)))
+                    .sum();
+                buf.push(sum / 16.);
+            }
+        }
+        Img::from_vec(buf, width).unwrap()
+    };
+    pass(&pass(img, (1, 0)), (0, 1))
+}
+
+/// the total [perceptual_error] of dithering each of the training images with `kernel`.
+pub fn score(
+    kernel: &Ditherer,
+    training: &[Img<f64>],
+    quantize: &impl Fn(f64) -> (f64, f64),
+) -> f64 {
+    training
+        .iter()
+        .map(|img| perceptual_error(img, &kernel.dither(img.clone(), quantize)))
+        .sum()
+}
+
+/// search for error-diffusion weights that minimize the [score] over the training images, starting from `start`.
+///
+/// the search keeps `start`'s offsets, multiplies its weights by `resolution` and rounds them to integers, then
+/// hill-climbs for `iterations` steps: each step nudges one randomly chosen weight up or down by one and keeps the
+/// change if it lowers the score. `div` is always the sum of the weights, so the kernel spreads exactly the error it carries.
+/// the search is deterministic for a given `seed`. returns the best kernel and its score.
+/// ```
+/// # use dither::prelude::*;
+/// # use dither::ditherer::{optimize, FLOYD_STEINBERG};
+/// # use std::num::NonZeroU32;
+/// let img = Img::new((0..64).map(|x| f64::from(x) * 4.), 8).unwrap();
+/// let quantize = dither::create_quantize_n_bits_func(1).unwrap();
+/// let training = [img];
+/// let (kernel, score) = optimize::optimize(&FLOYD_STEINBERG, &training, &quantize, 20, NonZeroU32::MIN, 1);
+/// assert!(score <= optimize::score(&FLOYD_STEINBERG, &training, &quantize));
+/// assert_eq!(kernel.div, kernel.offsets.iter().map(|&(_, _, mul)| mul).sum::<f64>());
+/// ```
+pub fn optimize(
+    start: &Ditherer,
+    training: &[Img<f64>],
+    quantize: &impl Fn(f64) -> (f64, f64),
+    iterations: usize,
+    resolution: NonZeroU32,
+    seed: u64,
+) -> (Kernel, f64) {
+    let mut best = Kernel::from(start);
+    if best.offsets.is_empty() {
+        let score = score(&best.ditherer(), training, quantize);
+        return (best, score);
+    }
+    for (_, _, mul) in best.offsets.iter_mut() {
+        *mul = f64::round(*mul * f64::from(resolution.get())).max(0.);
+    }
+    best.div = best.offsets.iter().map(|&(_, _, mul)| mul).sum();
+    let mut best_score = score(&best.ditherer(), training, quantize);
+
+    // xorshift64: we don't need good randomness, just a reproducible walk.
+    let mut state = seed.max(1);
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for _ in 0..iterations {
+        let r = next();
+        let i = (r >> 1) as usize % best.offsets.len();
+        let delta = if r & 1 == 0 { 1. } else { -1. };
+
+        let mut candidate = best.clone();
+        let mul = &mut candidate.offsets[i].2;
+        if *mul + delta < 0. {
+            continue;
+        }
+        *mul += delta;
+        candidate.div += delta;
+        if candidate.div <= 0. {
+            continue;
+        }
+
+        let candidate_score = score(&candidate.ditherer(), training, quantize);
+        if candidate_score < best_score {
+            best = candidate;
+            best_score = candidate_score;
+        }
+    }
+    (best, best_score)
+}
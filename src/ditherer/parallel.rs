@@ -0,0 +1,182 @@
+//! Multi-threaded wavefront error diffusion. See [Ditherer::dither_parallel].
+use super::{Cancel, Ditherer};
+use crate::pixel::Pixel;
+use crate::quantize::Quantizer;
+use crate::{Error, Img, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+impl<'a> Ditherer<'a> {
+    /// dither an image on up to `threads` threads, producing output identical to [Dither::dither][super::Dither::dither].
+    ///
+    /// rows are handed out round-robin, and each row trails the row above it by the kernel's horizontal reach,
+    /// so every pixel sees exactly the error it would have seen sequentially, added in the same order.
+    /// each thread gets its own clone of `quantize`, so a stateful quantizer only sees the rows its thread dithers;
+    /// quantizers that depend only on each pixel and its position behave exactly as they would sequentially.
+    /// ```
+    /// # use dither::prelude::*;
+    /// # use dither::ditherer::STUCKI;
+    /// let img: Img<f64> = Img::new((0..1200).map(|x| f64::from(x % 255)), 40).unwrap();
+    /// let quantize = dither::create_quantize_n_bits_func(1).unwrap();
+    /// assert_eq!(
+    ///     STUCKI.dither_parallel(img.clone(), &quantize, 4),
+    ///     STUCKI.dither(img, &quantize)
+    /// );
+    /// ```
+    pub fn dither_parallel<P>(
+        &self,
+        img: Img<P>,
+        quantize: impl Quantizer<P> + Clone + Send,
+        threads: usize,
+    ) -> Img<P>
+    where
+        P: Pixel + Send + Sync,
+    {
+        self.dither_parallel_with_progress(img, quantize, |_, _| {}, &Cancel::new(), threads)
+            .unwrap()
+    }
+
+    /// [Ditherer::dither_parallel] with progress reporting and cancellation, like [Ditherer::dither_with_progress].
+    /// rows can finish out of order, so `progress(rows_done, total_rows)` counts them rather than naming them.
+    /// it's called from whichever thread finished the row, but never from two at once, so `rows_done` only goes up.
+    /// ```
+    /// # use dither::prelude::*;
+    /// # use dither::ditherer::{Cancel, STUCKI};
+    /// # use std::sync::atomic::{AtomicUsize, Ordering};
+    /// let img: Img<f64> = Img::new((0..1200).map(|x| f64::from(x % 255)), 40).unwrap();
+    /// let quantize = dither::create_quantize_n_bits_func(1).unwrap();
+    /// let cancel = Cancel::new();
+    /// let calls = AtomicUsize::new(0);
+    /// let got = STUCKI.dither_parallel_with_progress(
+    ///     img.clone(),
+    ///     &quantize,
+    ///     |_, total| assert_eq!(calls.fetch_add(1, Ordering::Relaxed) < total, true),
+    ///     &cancel,
+    ///     4,
+    /// );
+    /// assert_eq!(got.unwrap(), STUCKI.dither(img.clone(), &quantize));
+    /// assert_eq!(calls.into_inner(), 30);
+    ///
+    /// cancel.cancel();
+    /// assert!(matches!(
+    ///     STUCKI.dither_parallel_with_progress(img, &quantize, |_, _| {}, &cancel, 4),
+    ///     Err(Error::Cancelled)
+    /// ));
+    /// ```
+    pub fn dither_parallel_with_progress<P>(
+        &self,
+        img: Img<P>,
+        quantize: impl Quantizer<P> + Clone + Send,
+        progress: impl Fn(usize, usize) + Sync,
+        cancel: &Cancel,
+        threads: usize,
+    ) -> Result<Img<P>>
+    where
+        P: Pixel + Send + Sync,
+    {
+        let (width, height) = (img.width() as usize, img.height() as usize);
+        let threads = usize::min(threads, height);
+        if threads <= 1 {
+            return self.dither_with_progress(img, quantize, progress, cancel);
+        }
+
+        let offsets: Vec<(isize, isize, f64)> = self.forward_offsets().collect();
+        // how many columns a row must trail the row above it. a pixel gets error from at most `-min_dx` columns to
+        // its right in the rows above, and all of it must arrive before any error from the rows below it,
+        // which comes from at most `max_dx` columns to its left.
+        let max_dx = offsets.iter().map(|&(dx, _, _)| dx).fold(0, isize::max);
+        let min_dx = offsets
+            .iter()
+            .filter(|&&(_, dy, _)| dy > 0)
+            .map(|&(dx, _, _)| dx)
+            .fold(0, isize::min);
+        let lag = (max_dx - min_dx) as usize;
+
+        let mut buf = img.into_vec();
+        let mut spillover = vec![P::zero(); buf.len()];
+        let spillover = SharedBuf(spillover.as_mut_ptr(), spillover.len());
+        let columns_done: Vec<AtomicUsize> = (0..height).map(|_| AtomicUsize::new(0)).collect();
+        let rows_done = Mutex::new(0);
+
+        let mut rows_by_thread: Vec<Vec<(usize, &mut [P])>> =
+            (0..threads).map(|_| Vec::new()).collect();
+        for (y, row) in buf.chunks_mut(width).enumerate() {
+            rows_by_thread[y % threads].push((y, row));
+        }
+
+        std::thread::scope(|s| {
+            for rows in rows_by_thread {
+                let (offsets, spillover, columns_done, rows_done, progress) =
+                    (&offsets, &spillover, &columns_done, &rows_done, &progress);
+                let mut quantize = quantize.clone();
+                s.spawn(move || {
+                    for (y, row) in rows {
+                        if cancel.is_cancelled() {
+                            return;
+                        }
+                        for (x, p) in row.iter_mut().enumerate() {
+                            if y > 0 {
+                                let ready = usize::min(x + lag + 1, width);
+                                while columns_done[y - 1].load(Ordering::Acquire) < ready {
+                                    // the row above may have given up.
+                                    if cancel.is_cancelled() {
+                                        return;
+                                    }
+                                    std::hint::spin_loop();
+                                    std::thread::yield_now();
+                                }
+                            }
+                            let i = y * width + x;
+                            // SAFETY: every write to spillover[i] comes from a pixel the wait above has
+                            // already seen completed, and nothing writes to it after this pixel.
+                            let carried = unsafe { spillover.get(i) };
+                            let (quantized, spill) = quantize.quantize(p.plus(carried), (x, y));
+                            *p = quantized;
+
+                            for &(dx, dy, mul) in offsets {
+                                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                                if nx < 0 || nx >= width as isize || ny >= height as isize {
+                                    continue;
+                                }
+                                let j = ny as usize * width + nx as usize;
+                                // SAFETY: the lag between rows orders all the writes to spillover[j].
+                                let stored_spill = unsafe { spillover.get_mut(j) };
+                                *stored_spill = stored_spill.plus(&spill.scale(mul, self.div));
+                            }
+                            columns_done[y].store(x + 1, Ordering::Release);
+                        }
+                        let mut done = rows_done.lock().unwrap();
+                        *done += 1;
+                        progress(*done, height);
+                    }
+                });
+            }
+        });
+        if rows_done.into_inner().unwrap() < height {
+            return Err(Error::Cancelled);
+        }
+        Ok(Img::from_vec(buf, width as u32).unwrap())
+    }
+}
+
+/// a buffer shared between the wavefront's threads. the wavefront, not the type system, keeps accesses from overlapping.
+struct SharedBuf<P>(*mut P, usize);
+
+unsafe impl<P: Send> Send for SharedBuf<P> {}
+unsafe impl<P: Send> Sync for SharedBuf<P> {}
+
+impl<P> SharedBuf<P> {
+    /// # Safety
+    /// no other thread may be writing to index `i`.
+    unsafe fn get(&self, i: usize) -> &P {
+        assert!(i < self.1);
+        &*self.0.add(i)
+    }
+    /// # Safety
+    /// no other thread may be reading or writing index `i`.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn get_mut(&self, i: usize) -> &mut P {
+        assert!(i < self.1);
+        &mut *self.0.add(i)
+    }
+}
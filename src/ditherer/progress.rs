@@ -0,0 +1,72 @@
+//! Progress reporting and cancellation for long-running dithers. See [Ditherer::dither_with_progress].
+use super::{Ditherer, RowDitherer};
+use crate::pixel::Pixel;
+use crate::quantize::Quantizer;
+use crate::{Error, Img, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A cancellation token for a running dither. Clones share the same flag: hand one to the dither,
+/// and [cancel][Cancel::cancel] through another, i.e, from a GUI's cancel button on another thread.
+/// ```
+/// # use dither::ditherer::Cancel;
+/// let token = Cancel::new();
+/// let button = token.clone();
+/// button.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// stop any dither using this token (or a clone of it) at the start of its next row.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl<'a> Ditherer<'a> {
+    /// dither an image like [Dither::dither][super::Dither::dither], calling `progress(rows_done, total_rows)`
+    /// after each row, and checking `cancel` before each one. a cancelled dither returns [Error::Cancelled].
+    /// ```
+    /// # use dither::prelude::*;
+    /// # use dither::ditherer::{Cancel, STUCKI};
+    /// let img: Img<f64> = Img::new((0..1200).map(|x| f64::from(x % 255)), 40).unwrap();
+    /// let quantize = dither::create_quantize_n_bits_func(1).unwrap();
+    /// let cancel = Cancel::new();
+    ///
+    /// let mut reported = Vec::new();
+    /// let got = STUCKI.dither_with_progress(img.clone(), &quantize, |done, total| reported.push((done, total)), &cancel);
+    /// assert_eq!(got.unwrap(), STUCKI.dither(img.clone(), &quantize));
+    /// assert_eq!(reported.last(), Some(&(30, 30)));
+    ///
+    /// // cancel partway through
+    /// let got = STUCKI.dither_with_progress(img, &quantize, |done, _| if done == 10 { cancel.cancel() }, &cancel);
+    /// assert!(matches!(got, Err(Error::Cancelled)));
+    /// ```
+    pub fn dither_with_progress<P: Pixel>(
+        &self,
+        img: Img<P>,
+        quantize: impl Quantizer<P>,
+        mut progress: impl FnMut(usize, usize),
+        cancel: &Cancel,
+    ) -> Result<Img<P>> {
+        let (width, height) = (img.width(), img.height() as usize);
+        let mut stream = RowDitherer::new(self, width as usize, quantize);
+        let mut buf = img.into_vec();
+        for row in buf.chunks_mut(width as usize) {
+            if cancel.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            stream.push_row(row);
+            progress(stream.rows_done(), height);
+        }
+        Ok(Img::from_vec(buf, width).unwrap())
+    }
+}
@@ -0,0 +1,564 @@
+//! An open, object-safe registry of dithering algorithms, keyed by name.
+//!
+//! The [Dither] trait takes its quantizer as an `impl Quantizer`, so it can't be made into a trait object.
+//! [Algorithm] can: it takes its quantizer as a `&mut dyn Quantizer`, for each of the pixel types the CLI works in.
+//! Downstream crates can [register] their own algorithms, then run the CLI with [crate::cli::main]; they'll show up
+//! in `--dither` and `--help` alongside the built-in kernels.
+//! ```
+//! # use dither::prelude::*;
+//! # use dither::ditherer::registry::{self, Algorithm, DynDither, Entry};
+//! # use dither::quantize::Quantizer;
+//! /// quantize each pixel on its own, without spreading any error.
+//! #[derive(Debug)]
+//! struct Posterize;
+//!
+//! impl<P: Pixel> DynDither<P> for Posterize {
+//!     fn dither_dyn(&self, img: Img<P>, quantize: &mut dyn Quantizer<P>) -> Img<P> {
+//!         let width = img.width() as usize;
+//!         let mut i = 0;
+//!         img.convert_with(|p| {
+//!             let at = (i % width, i / width);
+//!             i += 1;
+//!             quantize.quantize(p, at).0
+//!         })
+//!     }
+//! }
+//! impl Algorithm for Posterize {}
+//!
+//! registry::register(
+//!     Entry::new("posterize", "quantize each pixel on its own", |_| Ok(Box::new(Posterize)))
+//!         .alias("none"),
+//! );
+//! let selected: registry::Selected = "NONE".parse().unwrap();
+//! assert_eq!(selected.to_string(), "posterize");
+//! ```
+use super::{Cancel, Dither, Ditherer};
+use crate::color::RGB;
+use crate::pixel::Pixel;
+use crate::quantize::{CloneQuantizer, Quantizer};
+use crate::Img;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// dither an image of `P` through a trait object. See [Algorithm].
+pub trait DynDither<P> {
+    /// dither an image, like [Dither::dither].
+    fn dither_dyn(&self, img: Img<P>, quantize: &mut dyn Quantizer<P>) -> Img<P>;
+
+    /// dither an image on up to `threads` threads, like [Ditherer::dither_parallel].
+    /// by default, this ignores `threads` and calls [DynDither::dither_dyn].
+    fn dither_parallel_dyn(
+        &self,
+        img: Img<P>,
+        quantize: &dyn CloneQuantizer<P>,
+        threads: usize,
+    ) -> Img<P> {
+        let _ = threads;
+        self.dither_dyn(img, &mut quantize.clone_box())
+    }
+
+    /// dither an image on up to `threads` threads like [DynDither::dither_parallel_dyn], calling
+    /// `progress(rows_done, total_rows)` as rows finish and stopping with [Error::Cancelled][crate::Error::Cancelled]
+    /// once `cancel` is set. See [Ditherer::dither_parallel_with_progress].
+    /// by default, this only checks `cancel` before starting, and reports progress once, when it's done;
+    /// [Ditherer]s report every row.
+    fn dither_with_progress_dyn(
+        &self,
+        img: Img<P>,
+        quantize: &dyn CloneQuantizer<P>,
+        progress: &(dyn Fn(usize, usize) + Sync),
+        cancel: &Cancel,
+        threads: usize,
+    ) -> crate::Result<Img<P>> {
+        if cancel.is_cancelled() {
+            return Err(crate::Error::Cancelled);
+        }
+        let height = img.height() as usize;
+        let img = self.dither_parallel_dyn(img, quantize, threads);
+        progress(height, height);
+        Ok(img)
+    }
+}
+
+/// An object-safe dithering algorithm: a [DynDither] for grayscale, [RGB] and working-[space][crate::color::space]
+/// images in both [f32] and [f64]. implement [DynDither] generically over [Pixel]s to get all six.
+pub trait Algorithm:
+    DynDither<f32>
+    + DynDither<f64>
+    + DynDither<RGB<f32>>
+    + DynDither<RGB<f64>>
+    + DynDither<[f32; 3]>
+    + DynDither<[f64; 3]>
+    + Send
+    + Sync
+{
+    /// the error-diffusion kernel behind this algorithm, if it has one. algorithms without one can't be used with
+    /// kernel-only features like the [fixed][super::fixed] pipeline.
+    fn kernel(&self) -> Option<Ditherer<'_>> {
+        None
+    }
+}
+
+impl<'a, P: Pixel + Send + Sync> DynDither<P> for Ditherer<'a> {
+    fn dither_dyn(&self, img: Img<P>, quantize: &mut dyn Quantizer<P>) -> Img<P> {
+        self.dither(img, quantize)
+    }
+    fn dither_parallel_dyn(
+        &self,
+        img: Img<P>,
+        quantize: &dyn CloneQuantizer<P>,
+        threads: usize,
+    ) -> Img<P> {
+        self.dither_parallel(img, quantize.clone_box(), threads)
+    }
+    fn dither_with_progress_dyn(
+        &self,
+        img: Img<P>,
+        quantize: &dyn CloneQuantizer<P>,
+        progress: &(dyn Fn(usize, usize) + Sync),
+        cancel: &Cancel,
+        threads: usize,
+    ) -> crate::Result<Img<P>> {
+        self.dither_parallel_with_progress(img, quantize.clone_box(), progress, cancel, threads)
+    }
+}
+
+impl<'a> Algorithm for Ditherer<'a> {
+    fn kernel(&self) -> Option<Ditherer<'_>> {
+        Some(self.clone())
+    }
+}
+
+/// builds an [Algorithm] from its [Params].
+pub type Build = dyn Fn(&Params) -> Result<Box<dyn Algorithm>, Error> + Send + Sync;
+
+/// A registered algorithm: its name, aliases, description, and parameters, and how to build it.
+#[derive(Clone)]
+pub struct Entry {
+    name: String,
+    aliases: Vec<String>,
+    description: String,
+    parameters: Vec<Parameter>,
+    build: Arc<Build>,
+}
+
+/// A parameter an [Entry] accepts, as `--dither=name:parameter=value`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Parameter {
+    pub name: String,
+    pub default: String,
+    pub description: String,
+}
+
+/// The parameters an algorithm was selected with, with defaults filled in. See [Entry::parameter].
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Params {
+    algorithm: String,
+    values: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An error selecting an algorithm from the registry.
+pub enum Error {
+    /// No registered algorithm has this name or alias.
+    UnknownAlgorithm(String),
+    /// The algorithm doesn't take a parameter with this name.
+    UnknownParameter {
+        algorithm: String,
+        parameter: String,
+    },
+    /// The parameter's value couldn't be parsed or is out of range.
+    BadParameter {
+        algorithm: String,
+        parameter: String,
+        value: String,
+    },
+}
+
+impl Entry {
+    /// create an entry with no aliases or parameters.
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        build: impl Fn(&Params) -> Result<Box<dyn Algorithm>, Error> + Send + Sync + 'static,
+    ) -> Self {
+        Entry {
+            name: name.into(),
+            aliases: Vec::new(),
+            description: description.into(),
+            parameters: Vec::new(),
+            build: Arc::new(build),
+        }
+    }
+
+    /// create an entry for an error-diffusion kernel, named after the ditherer.
+    pub fn kernel(ditherer: Ditherer<'static>, description: impl Into<String>) -> Self {
+        Entry::new(ditherer.to_string(), description, move |_| {
+            Ok(Box::new(ditherer.clone()))
+        })
+    }
+
+    /// add an alias the entry can also be selected by.
+    pub fn alias(mut self, alias: impl Into<String>) -> Self {
+        self.aliases.push(alias.into());
+        self
+    }
+
+    /// add a parameter, selected as `--dither=name:parameter=value`. See [Params::get].
+    pub fn parameter(
+        mut self,
+        name: impl Into<String>,
+        default: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.parameters.push(Parameter {
+            name: name.into(),
+            default: default.into(),
+            description: description.into(),
+        });
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+    pub fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        std::iter::once(&self.name)
+            .chain(&self.aliases)
+            .any(|n| n.eq_ignore_ascii_case(name))
+    }
+}
+
+impl std::fmt::Debug for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Entry")
+            .field("name", &self.name)
+            .field("aliases", &self.aliases)
+            .field("description", &self.description)
+            .field("parameters", &self.parameters)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Params {
+    /// the value of a parameter, parsed as `T`.
+    /// ```
+    /// # use dither::ditherer::registry::{self, Entry, Error};
+    /// # use dither::ditherer::FLOYD_STEINBERG;
+    /// registry::register(
+    ///     Entry::new("scaled-floyd", "floyd-steinberg, spreading only some of the error", |params| {
+    ///         let percent: u8 = params.get("percent")?;
+    ///         let _ = percent; // ... build a kernel scaled to percent / 100
+    ///         Ok(Box::new(FLOYD_STEINBERG))
+    ///     })
+    ///     .parameter("percent", "100", "how much of the error to spread"),
+    /// );
+    /// assert!("scaled-floyd:percent=50".parse::<registry::Selected>().is_ok());
+    /// assert_eq!(
+    ///     "scaled-floyd:percent=lots".parse::<registry::Selected>().unwrap_err(),
+    ///     Error::BadParameter {
+    ///         algorithm: "scaled-floyd".to_string(),
+    ///         parameter: "percent".to_string(),
+    ///         value: "lots".to_string()
+    ///     }
+    /// );
+    /// ```
+    pub fn get<T: FromStr>(&self, name: &str) -> Result<T, Error> {
+        let unknown = || Error::UnknownParameter {
+            algorithm: self.algorithm.clone(),
+            parameter: name.to_string(),
+        };
+        let value = self.values.get(name).ok_or_else(unknown)?;
+        value.parse().map_err(|_| Error::BadParameter {
+            algorithm: self.algorithm.clone(),
+            parameter: name.to_string(),
+            value: value.clone(),
+        })
+    }
+}
+
+/// A set of [Entries][Entry], searched by name or alias. [Registry::default] holds the built-in kernels.
+#[derive(Clone, Debug)]
+pub struct Registry {
+    entries: Vec<Entry>,
+}
+
+impl Registry {
+    /// a registry without any entries.
+    pub const fn empty() -> Self {
+        Registry {
+            entries: Vec::new(),
+        }
+    }
+
+    /// add an entry. it takes precedence over earlier entries with the same name or aliases.
+    pub fn register(&mut self, entry: Entry) {
+        self.entries.push(entry);
+    }
+
+    /// the registered entries, in the order they were registered.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// the entry with this name or alias, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&Entry> {
+        self.entries.iter().rev().find(|entry| entry.matches(name))
+    }
+
+    /// select and build an algorithm from a spec like `"name"` or `"name:parameter=value,parameter=value"`.
+    pub fn select(&self, spec: &str) -> Result<Selected, Error> {
+        let (name, args) = match spec.trim().split_once(':') {
+            Some((name, args)) => (name.trim(), args),
+            None => (spec.trim(), ""),
+        };
+        let entry = self
+            .get(name)
+            .ok_or_else(|| Error::UnknownAlgorithm(name.to_string()))?;
+
+        let mut params = Params {
+            algorithm: entry.name.clone(),
+            values: entry
+                .parameters
+                .iter()
+                .map(|p| (p.name.clone(), p.default.clone()))
+                .collect(),
+        };
+        for arg in args.split(',').map(str::trim).filter(|arg| !arg.is_empty()) {
+            let (key, value) = arg.split_once('=').unwrap_or((arg, ""));
+            match params.values.get_mut(key.trim()) {
+                Some(stored) => *stored = value.trim().to_string(),
+                None => {
+                    return Err(Error::UnknownParameter {
+                        algorithm: entry.name.clone(),
+                        parameter: key.trim().to_string(),
+                    })
+                }
+            }
+        }
+        let algorithm = (entry.build)(&params)?;
+        Ok(Selected {
+            params,
+            algorithm: Arc::from(algorithm),
+        })
+    }
+
+    /// a description of every entry, for `--help`.
+    pub fn help(&self) -> String {
+        let mut help = String::new();
+        for entry in &self.entries {
+            help.push_str(&format!("- \"{}\"", entry.name));
+            for alias in &entry.aliases {
+                help.push_str(&format!(", \"{}\"", alias));
+            }
+            help.push_str(&format!(": {}\n", entry.description));
+            for p in &entry.parameters {
+                help.push_str(&format!(
+                    "    {}={}: {}\n",
+                    p.name, p.default, p.description
+                ));
+            }
+        }
+        help
+    }
+}
+
+/// the built-in kernels, with their descriptions and aliases. [Registry::default] holds an entry for each,
+/// and [Ditherer]'s [FromStr] looks names up among them, so both accept the same names.
+const BUILT_IN: [(Ditherer<'static>, &str, &[&str]); 6] = [
+    (
+        super::FLOYD_STEINBERG,
+        "floyd-steinberg. div=16 (default)",
+        &["steinberg", "floydsteinberg", "floyd steinberg"],
+    ),
+    (
+        super::ATKINSON,
+        "atkinson. div=8; only spreads 3/4 of the error",
+        &[],
+    ),
+    (super::STUCKI, "stucki. div=42", &[]),
+    (super::BURKES, "burkes. div=32", &[]),
+    (
+        super::JARVIS_JUDICE_NINKE,
+        "jarvis-judice-ninke. div=48",
+        &["judice", "ninke"],
+    ),
+    (super::SIERRA_3, "sierra 3. div=32", &["sierra"]),
+];
+
+/// the built-in kernel with this name or alias, ignoring case, as selected from [Registry::default].
+pub(crate) fn built_in(name: &str) -> Option<Ditherer<'static>> {
+    let entry = Registry::default().get(name)?.name.clone();
+    BUILT_IN
+        .iter()
+        .map(|(kernel, _, _)| kernel)
+        .find(|kernel| kernel.to_string() == entry)
+        .cloned()
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Registry::empty();
+        for (kernel, description, aliases) in BUILT_IN {
+            let entry = aliases
+                .iter()
+                .fold(Entry::kernel(kernel, description), |entry, &alias| {
+                    entry.alias(alias)
+                });
+            registry.register(entry);
+        }
+        registry
+    }
+}
+
+/// the global registry used by the CLI; it starts out as [Registry::default].
+pub fn global() -> &'static RwLock<Registry> {
+    static GLOBAL: OnceLock<RwLock<Registry>> = OnceLock::new();
+    GLOBAL.get_or_init(|| RwLock::new(Registry::default()))
+}
+
+/// add an entry to the [global] registry.
+pub fn register(entry: Entry) {
+    global()
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .register(entry)
+}
+
+/// An algorithm selected from a [Registry], along with the parameters it was built with.
+/// Parsing it with [FromStr] selects from the [global] registry. Corresponds to [Opt][crate::Opt] `--dither`
+#[derive(Clone)]
+pub struct Selected {
+    params: Params,
+    algorithm: Arc<dyn Algorithm>,
+}
+
+impl Selected {
+    /// the selected algorithm.
+    pub fn algorithm(&self) -> &dyn Algorithm {
+        &*self.algorithm
+    }
+    /// the parameters it was built with.
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// the algorithm's name and parameters, for use in a file name: `name-parameter-value-parameter-value`,
+    /// with anything but ASCII letters, digits, `.`, and `_` replaced by `-`. Use [Display][std::fmt::Display]
+    /// for messages.
+    /// ```
+    /// # use dither::ditherer::registry::{self, Entry};
+    /// # use dither::ditherer::FLOYD_STEINBERG;
+    /// registry::register(
+    ///     Entry::new("scaled floyd", "floyd-steinberg, spreading only some of the error", |_| {
+    ///         Ok(Box::new(FLOYD_STEINBERG))
+    ///     })
+    ///     .parameter("strength", "1", "how much of the error to spread"),
+    /// );
+    /// let selected: registry::Selected = "scaled floyd:strength=0.5".parse().unwrap();
+    /// assert_eq!(selected.to_string(), "scaled floyd:strength=0.5");
+    /// assert_eq!(selected.file_name(), "scaled-floyd-strength-0.5");
+    /// ```
+    pub fn file_name(&self) -> String {
+        let mut name = self.params.algorithm.clone();
+        for (parameter, value) in &self.params.values {
+            name.push_str(&format!("-{}-{}", parameter, value));
+        }
+        name.chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '_' => c,
+                _ => '-',
+            })
+            .collect()
+    }
+}
+
+impl std::ops::Deref for Selected {
+    type Target = dyn Algorithm;
+    fn deref(&self) -> &Self::Target {
+        &*self.algorithm
+    }
+}
+
+impl FromStr for Selected {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        global()
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .select(s)
+    }
+}
+
+impl Default for Selected {
+    fn default() -> Self {
+        Selected {
+            params: Params {
+                algorithm: super::FLOYD_STEINBERG.to_string(),
+                values: BTreeMap::new(),
+            },
+            algorithm: Arc::new(super::FLOYD_STEINBERG),
+        }
+    }
+}
+
+impl PartialEq for Selected {
+    fn eq(&self, other: &Self) -> bool {
+        self.params == other.params
+    }
+}
+
+impl std::fmt::Debug for Selected {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Selected({})", self)
+    }
+}
+
+/// the algorithm's name, followed by any parameters: `name:parameter=value,parameter=value`
+impl std::fmt::Display for Selected {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.params.algorithm)?;
+        for (i, (name, value)) in self.params.values.iter().enumerate() {
+            write!(f, "{}{}={}", if i == 0 { ':' } else { ',' }, name, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::UnknownAlgorithm(name) => write!(f, "unknown ditherer: {}", name),
+            Error::UnknownParameter {
+                algorithm,
+                parameter,
+            } => write!(
+                f,
+                "ditherer {} has no parameter \"{}\"",
+                algorithm, parameter
+            ),
+            Error::BadParameter {
+                algorithm,
+                parameter,
+                value,
+            } => write!(
+                f,
+                "bad value for ditherer {}'s parameter \"{}\": {}",
+                algorithm, parameter, value
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
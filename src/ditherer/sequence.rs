@@ -0,0 +1,151 @@
+//! Temporally coherent dithering for image sequences, i.e, the frames of an animation or a cutscene.
+//!
+//! Dithering each frame on its own makes the whole image "boil": a change anywhere shifts the error, and so
+//! the pattern, everywhere after it. A [Sequence] remembers each pixel's last output, and keeps it wherever the
+//! input hasn't changed by more than a tolerance, so static regions stay identical from frame to frame and only
+//! what moves is dithered again. Corresponds to [Opt][crate::Opt] `--sequence`.
+//! ```
+//! # use dither::prelude::*;
+//! # use dither::ditherer::sequence::Sequence;
+//! let quantize = dither::create_quantize_n_bits_func(1).unwrap();
+//! let mut sequence = Sequence::new(0.);
+//! let still: Img<f64> = Img::new((0..64).map(|x| f64::from(x) * 4.), 8).unwrap();
+//! let mut moved = still.clone();
+//! moved[(0, 0)] = 255.;
+//!
+//! let first = sequence.dither(still, &quantize, |img, q| Ditherer::default().dither(img, q));
+//! let second = sequence.dither(moved, &quantize, |img, q| Ditherer::default().dither(img, q));
+//! // only the pixel that changed can be different.
+//! assert!(first.iter().zip(second.iter()).skip(1).all(|(a, b)| a == b));
+//! ```
+use crate::pixel::Pixel;
+use crate::quantize::Quantizer;
+use crate::Img;
+use std::sync::Arc;
+
+/// The state of a sequence between frames. Feed it every frame in order with [Sequence::dither].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sequence<P> {
+    tolerance: f64,
+    /// the input each pixel's output was last decided for.
+    reference: Vec<P>,
+    /// each pixel's output in the previous frame.
+    output: Vec<P>,
+    size: (u32, u32),
+}
+
+impl<P: Pixel> Sequence<P>
+where
+    P::Channel: Into<f64>,
+{
+    /// a new sequence. a pixel keeps its previous output while the manhattan distance between its input and the
+    /// input that output was decided for is at most `tolerance`.
+    pub fn new(tolerance: f64) -> Self {
+        Sequence {
+            tolerance,
+            reference: Vec::new(),
+            output: Vec::new(),
+            size: (0, 0),
+        }
+    }
+
+    /// dither the next frame. `dither` dithers it in space with the [Coherent] quantizer it's given,
+    /// i.e, with any algorithm, on any number of threads. a frame of a different size starts the sequence over.
+    /// # Panics
+    /// if `dither` changes the size of the image.
+    pub fn dither<Q: Quantizer<P>>(
+        &mut self,
+        frame: Img<P>,
+        quantize: Q,
+        dither: impl FnOnce(Img<P>, Coherent<P, Q>) -> Img<P>,
+    ) -> Img<P> {
+        if frame.size() != self.size {
+            self.reference.clear();
+            self.output.clear();
+        }
+        let kept: Vec<Option<P>> = if self.reference.is_empty() {
+            vec![None; frame.len()]
+        } else {
+            frame
+                .iter()
+                .zip(&self.reference)
+                .zip(&self.output)
+                .map(|((p, reference), output)| {
+                    (distance(p, reference) <= self.tolerance).then(|| output.clone())
+                })
+                .collect()
+        };
+        let input = frame.clone();
+        let coherent = Coherent {
+            quantize,
+            kept: kept.into(),
+            width: frame.width() as usize,
+        };
+        let kept = Arc::clone(&coherent.kept);
+        let output = dither(frame, coherent);
+        assert_eq!(
+            output.size(),
+            input.size(),
+            "dithering changed the frame size"
+        );
+
+        if self.reference.is_empty() {
+            self.reference = input.iter().cloned().collect();
+        } else {
+            for ((reference, p), kept) in
+                self.reference.iter_mut().zip(input.iter()).zip(kept.iter())
+            {
+                if kept.is_none() {
+                    *reference = p.clone();
+                }
+            }
+        }
+        self.output = output.iter().cloned().collect();
+        self.size = output.size();
+        output
+    }
+}
+
+/// A quantizer for one frame of a [Sequence]: pixels that haven't changed keep their previous output, and, like
+/// [Flat][crate::color::palette::Flat]'s solid pixels, neither pick up nor spread error. the rest go to `Q`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coherent<P, Q> {
+    quantize: Q,
+    kept: Arc<[Option<P>]>,
+    width: usize,
+}
+
+impl<P: Clone, Q> Coherent<P, Q> {
+    fn kept(&self, (x, y): (usize, usize)) -> Option<P> {
+        self.kept.get(y * self.width + x).cloned().flatten()
+    }
+}
+
+impl<P: Pixel, Q: Quantizer<P>> Quantizer<P> for Coherent<P, Q> {
+    fn nearest(&mut self, p: &P, at: (usize, usize)) -> P {
+        match self.kept(at) {
+            Some(output) => output,
+            None => self.quantize.nearest(p, at),
+        }
+    }
+    fn error(&mut self, p: P, nearest: &P, at: (usize, usize)) -> P {
+        match self.kept(at) {
+            Some(_) => P::zero(),
+            None => self.quantize.error(p, nearest, at),
+        }
+    }
+    fn is_solid(&self, at: (usize, usize)) -> bool {
+        self.kept(at).is_some() || self.quantize.is_solid(at)
+    }
+}
+
+/// the manhattan distance between two pixels.
+fn distance<P: Pixel>(a: &P, b: &P) -> f64
+where
+    P::Channel: Into<f64>,
+{
+    a.channels()
+        .zip(b.channels())
+        .map(|(a, b)| f64::abs(a.into() - b.into()))
+        .sum()
+}
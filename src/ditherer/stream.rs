@@ -0,0 +1,231 @@
+//! Row-by-row dithering with bounded memory. See [RowDitherer] and [Ditherer::dither_slice].
+use super::Ditherer;
+use crate::pixel::Pixel;
+use crate::quantize::Quantizer;
+
+impl<'a> Ditherer<'a> {
+    /// the length of the scratch buffer [Ditherer::dither_slice] needs for rows `width` pixels wide:
+    /// one row of error for the current row, and one for each row the kernel [reaches][Ditherer::reach] down.
+    /// ```
+    /// # use dither::ditherer::STUCKI;
+    /// assert_eq!(STUCKI.scratch_len(640), 3 * 640);
+    /// ```
+    pub fn scratch_len(&self, width: usize) -> usize {
+        (self.reach() + 1) * width
+    }
+
+    /// dither a caller-owned buffer of tightly-packed rows `width` pixels wide in place, without allocating.
+    /// `scratch` holds the error while dithering; its contents are overwritten.
+    /// The output is identical to [Dither::dither][super::Dither::dither].
+    /// ```
+    /// # use dither::prelude::*;
+    /// # use dither::ditherer::FLOYD_STEINBERG;
+    /// let quantize = dither::create_quantize_n_bits_func(1).unwrap();
+    /// let mut frame: Vec<f64> = (0..64).map(|x| f64::from(x) * 4.).collect();
+    /// let mut scratch = vec![0.; FLOYD_STEINBERG.scratch_len(8)];
+    /// let want = FLOYD_STEINBERG.dither(Img::new(frame.clone(), 8).unwrap(), &quantize);
+    ///
+    /// FLOYD_STEINBERG.dither_slice(&mut frame, 8, &mut scratch, &quantize);
+    /// assert_eq!(frame, want.into_vec());
+    /// ```
+    /// # Panics
+    /// if `buf.len()` isn't a multiple of `width`, or `scratch` is shorter than [Ditherer::scratch_len].
+    pub fn dither_slice<P>(
+        &self,
+        buf: &mut [P],
+        width: usize,
+        scratch: &mut [P],
+        quantize: impl Quantizer<P>,
+    ) where
+        P: Pixel,
+    {
+        assert!(
+            buf.len().is_multiple_of(width),
+            "buffer ends partway through a row"
+        );
+        self.dither_strided(buf, width, width, scratch, quantize)
+    }
+
+    /// dither a caller-owned buffer of rows `width` pixels wide in place, without allocating.
+    /// rows start every `stride` pixels; any padding between the end of one row and the start of the next is left alone,
+    /// and the last row doesn't need any. `scratch` holds the error while dithering; its contents are overwritten.
+    /// ```
+    /// # use dither::ditherer::ATKINSON;
+    /// let quantize = dither::create_quantize_n_bits_func(1).unwrap();
+    /// // two 2-pixel rows, padded out to a stride of 3
+    /// let mut frame = vec![200., 100., -1., 100., 200.];
+    /// let mut scratch = vec![0.; ATKINSON.scratch_len(2)];
+    /// ATKINSON.dither_strided(&mut frame, 2, 3, &mut scratch, quantize);
+    /// assert_eq!(frame[2], -1.);
+    /// ```
+    /// # Panics
+    /// if `stride < width`, `buf` ends partway through a row, or `scratch` is shorter than [Ditherer::scratch_len].
+    pub fn dither_strided<P>(
+        &self,
+        buf: &mut [P],
+        width: usize,
+        stride: usize,
+        scratch: &mut [P],
+        mut quantize: impl Quantizer<P>,
+    ) where
+        P: Pixel,
+    {
+        assert!(stride >= width, "stride must be at least the width");
+        if width == 0 {
+            return;
+        }
+        let errors = &mut scratch[..self.scratch_len(width)];
+        for err in errors.iter_mut() {
+            *err = P::zero();
+        }
+        for (y, row) in buf.chunks_mut(stride).enumerate() {
+            assert!(row.len() >= width, "buffer ends partway through a row");
+            diffuse_row(self, &mut row[..width], errors, y, &mut quantize);
+        }
+    }
+}
+
+/// A streaming ditherer: feed it rows from top to bottom, and it dithers each one in place as soon as it arrives.
+/// It only keeps as many rows of error as the kernel reaches down (see [Ditherer::reach]), plus the current row,
+/// rather than a buffer the size of the image. The output is identical to [Dither::dither][super::Dither::dither].
+/// ```
+/// # use dither::prelude::*;
+/// # use dither::ditherer::{RowDitherer, FLOYD_STEINBERG};
+/// let quantize = dither::create_quantize_n_bits_func(1).unwrap();
+/// let img: Img<f64> = Img::new((0..64).map(|x| f64::from(x) * 4.), 8).unwrap();
+///
+/// let mut stream = RowDitherer::new(&FLOYD_STEINBERG, 8, &quantize);
+/// let mut rows: Vec<Vec<f64>> = img.clone().into_vec().chunks(8).map(<[f64]>::to_vec).collect();
+/// for row in rows.iter_mut() {
+///     stream.push_row(row);
+/// }
+/// assert_eq!(rows.concat(), FLOYD_STEINBERG.dither(img, &quantize).into_vec());
+/// ```
+#[derive(Debug, Clone)]
+pub struct RowDitherer<'a, P, Q> {
+    ditherer: Ditherer<'a>,
+    errors: Vec<P>,
+    width: usize,
+    y: usize,
+    quantize: Q,
+}
+
+impl<'a, P, Q> RowDitherer<'a, P, Q>
+where
+    P: Pixel,
+    Q: Quantizer<P>,
+{
+    /// create a streaming ditherer for rows `width` pixels wide.
+    pub fn new(ditherer: &Ditherer<'a>, width: usize, quantize: Q) -> Self {
+        RowDitherer {
+            ditherer: ditherer.clone(),
+            errors: vec![P::zero(); (ditherer.reach() + 1) * width],
+            width,
+            y: 0,
+            quantize,
+        }
+    }
+
+    /// dither the next row in place.
+    /// # Panics
+    /// if `row.len()` isn't the width this ditherer was created with.
+    pub fn push_row(&mut self, row: &mut [P]) {
+        assert_eq!(row.len(), self.width, "row has the wrong width");
+        diffuse_row(
+            &self.ditherer,
+            row,
+            &mut self.errors,
+            self.y,
+            &mut self.quantize,
+        );
+        self.y += 1;
+    }
+
+    /// the number of rows dithered so far.
+    pub fn rows_done(&self) -> usize {
+        self.y
+    }
+
+    /// the number of pixels of error this ditherer keeps; [Ditherer::scratch_len], no matter how many rows it's fed.
+    pub fn buffered_len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// dither each row of `rows` in turn, yielding them as they're finished;
+    /// i.e, between an image decoder and an encoder.
+    /// ```
+    /// # use dither::prelude::*;
+    /// # use dither::ditherer::{RowDitherer, ATKINSON};
+    /// let quantize = dither::create_quantize_n_bits_func(1).unwrap();
+    /// let decoded = (0..4).map(|y| vec![f64::from(y) * 60.; 3]);
+    /// let encoded: Vec<Vec<f64>> = RowDitherer::new(&ATKINSON, 3, quantize).rows(decoded).collect();
+    /// assert_eq!(encoded.len(), 4);
+    /// ```
+    pub fn rows<I: IntoIterator<Item = Vec<P>>>(self, rows: I) -> Rows<'a, P, Q, I::IntoIter> {
+        Rows {
+            stream: self,
+            rows: rows.into_iter(),
+        }
+    }
+}
+
+/// An iterator over dithered rows. See [RowDitherer::rows].
+#[derive(Debug, Clone)]
+pub struct Rows<'a, P, Q, I> {
+    stream: RowDitherer<'a, P, Q>,
+    rows: I,
+}
+
+impl<'a, P, Q, I> Iterator for Rows<'a, P, Q, I>
+where
+    P: Pixel,
+    Q: Quantizer<P>,
+    I: Iterator<Item = Vec<P>>,
+{
+    type Item = Vec<P>;
+    fn next(&mut self) -> Option<Vec<P>> {
+        let mut row = self.rows.next()?;
+        self.stream.push_row(&mut row);
+        Some(row)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+/// dither row `y` in place, spreading its error through `errors`: a ring buffer holding the error for
+/// rows `y..y + errors.len() / row.len()`. the current row's error is zeroed afterwards, so it can be reused
+/// for the next row that lands in the same slot.
+pub(super) fn diffuse_row<P>(
+    ditherer: &Ditherer,
+    row: &mut [P],
+    errors: &mut [P],
+    y: usize,
+    quantize: &mut impl Quantizer<P>,
+) where
+    P: Pixel,
+{
+    let width = row.len();
+    if width == 0 {
+        return;
+    }
+    let rows = errors.len() / width;
+    let current = (y % rows) * width;
+    for (x, p) in row.iter_mut().enumerate() {
+        let (quantized, spill) = quantize.quantize(p.plus(&errors[current + x]), (x, y));
+        *p = quantized;
+
+        // add spillover matrices
+        for (dx, dy, mul) in ditherer.forward_offsets() {
+            let nx = x as isize + dx;
+            if nx < 0 || nx >= width as isize {
+                continue;
+            }
+            let j = ((y + dy as usize) % rows) * width + nx as usize;
+            errors[j] = errors[j].plus(&spill.scale(mul, ditherer.div));
+        }
+    }
+    for err in &mut errors[current..current + width] {
+        *err = P::zero();
+    }
+}
@@ -0,0 +1,88 @@
+//! Temporal dithering: split an image into a set of low-depth frames whose average over time approximates it,
+//! for displays that can only switch each pixel on or off per refresh, like LED matrices and e-paper.
+//! Each frame is dithered in space, and whatever intensity a pixel didn't get carries over into its next frame,
+//! so the error is spread across both time and space.
+//! ```
+//! # use dither::prelude::*;
+//! # use dither::ditherer::temporal;
+//! let quantize = dither::create_quantize_n_bits_func(1).unwrap();
+//! let gray: Img<f64> = Img::new(vec![64.; 64], 8).unwrap();
+//! let frames = temporal::dither_frames(&gray, 4, |frame| Ditherer::default().dither(frame, &quantize));
+//! // every pixel is lit in exactly one frame of four: on average, 63.75.
+//! for i in 0..64 {
+//!     assert_eq!(frames.iter().filter(|frame| frame.iter().nth(i) == Some(&255.)).count(), 1);
+//! }
+//! ```
+use crate::pixel::Pixel;
+use crate::Img;
+
+/// dither `img` into `frames` frames whose average approximates it. `dither` dithers a single frame in space;
+/// i.e, with any algorithm and quantizer, on any number of threads.
+/// # Panics
+/// if `dither` changes the size of the image.
+pub fn dither_frames<P: Pixel>(
+    img: &Img<P>,
+    frames: usize,
+    mut dither: impl FnMut(Img<P>) -> Img<P>,
+) -> Vec<Img<P>> {
+    let width = img.width();
+    // the intensity each pixel is owed from the frames so far.
+    let mut owed: Vec<P> = vec![P::zero(); img.len()];
+    (0..frames)
+        .map(|_| {
+            let target: Vec<P> = img.iter().zip(&owed).map(|(p, o)| p.plus(o)).collect();
+            let frame = dither(Img::from_vec(target.clone(), width).unwrap());
+            assert_eq!(frame.size(), img.size(), "dithering changed the frame size");
+            for ((o, t), f) in owed.iter_mut().zip(target).zip(frame.iter()) {
+                *o = t.plus(&f.scale(-1., 1.));
+            }
+            frame
+        })
+        .collect()
+}
+
+/// the level index of a quantized channel at a `--depth` of `depth`; i.e, `0` for `0` and `depth` for `255`.
+/// ```
+/// # use dither::ditherer::temporal;
+/// assert_eq!(temporal::level(255, 1), 1);
+/// assert_eq!(temporal::level(170, 3), 2);
+/// ```
+pub fn level(value: u8, depth: u8) -> u8 {
+    (f64::from(value) * f64::from(depth) / 255.).round() as u8
+}
+
+/// the number of bits [pack] stores each level in at a `--depth` of `depth`.
+/// ```
+/// # use dither::ditherer::temporal;
+/// assert_eq!(temporal::bits_per_level(1), 1);
+/// assert_eq!(temporal::bits_per_level(4), 3);
+/// ```
+pub fn bits_per_level(depth: u8) -> u32 {
+    u8::BITS - depth.leading_zeros()
+}
+
+/// pack a frame's [level]s into a bitstream: rows of `row_len` levels, each stored in [bits_per_level] bits,
+/// most significant bit first, with every row padded out to a whole byte.
+/// ```
+/// # use dither::ditherer::temporal;
+/// // two rows of three 1-bit levels
+/// assert_eq!(temporal::pack(&[1, 0, 1, 0, 1, 1], 3, 1), vec![0b1010_0000, 0b0110_0000]);
+/// ```
+pub fn pack(levels: &[u8], row_len: usize, depth: u8) -> Vec<u8> {
+    let bits = bits_per_level(depth) as usize;
+    let row_bytes = (row_len * bits).div_ceil(8);
+    let mut packed = Vec::with_capacity(levels.len().div_ceil(row_len.max(1)) * row_bytes);
+    for row in levels.chunks(row_len.max(1)) {
+        let start = packed.len();
+        packed.resize(start + row_bytes, 0);
+        for (i, &level) in row.iter().enumerate() {
+            for b in 0..bits {
+                if level >> (bits - 1 - b) & 1 == 1 {
+                    let bit = i * bits + b;
+                    packed[start + bit / 8] |= 0x80 >> (bit % 8);
+                }
+            }
+        }
+    }
+    packed
+}
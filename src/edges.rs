@@ -0,0 +1,206 @@
+//! Edge-line overlay, for an ink-outlined look: find the edges in an image's luminance, and draw them as solid
+//! lines over the dithered output. At 1 bit, dithering alone loses silhouettes; outlining them is how
+//! *Return of the Obra Dinn* keeps its scenes readable. Corresponds to [Opt][crate::Opt] `--edges`.
+//! ```
+//! # use dither::prelude::*;
+//! # use dither::edges::{self, Detector};
+//! // a dark left half and a light right half.
+//! let img: Img<RGB<u8>> = Img::new((0..64).map(|i| RGB::from([if i % 8 < 4 { 40 } else { 200 }; 3])), 8).unwrap();
+//! let luma = edges::luma(&img);
+//! let lines = edges::detect(&luma, Detector::Canny, 64., 1);
+//! let mut dithered = Ditherer::default().dither(luma, dither::create_quantize_n_bits_func(1).unwrap());
+//! // gray ink, to tell it apart from the dithering.
+//! edges::overlay(&mut dithered, &lines, 128.);
+//! assert!((0..8).all(|y| dithered[(3, y)] == 128. || dithered[(4, y)] == 128.));
+//! assert!((0..8).all(|y| dithered[(0, y)] != 128. && dithered[(7, y)] != 128.));
+//! ```
+use crate::color::RGB;
+use crate::Img;
+
+/// An edge detector. Corresponds to [Opt][crate::Opt] `--edge-detector`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum Detector {
+    /// every pixel whose gradient is at least the threshold: thick, soft edges.
+    #[value(name = "sobel")]
+    Sobel,
+    /// Canny's detector: smooth, keep only the ridge of each gradient, and follow edges down to half the
+    /// threshold from pixels that reach it. thin, connected lines.
+    #[default]
+    #[value(name = "canny")]
+    Canny,
+}
+
+/// the luminance of each pixel, from [RGB::to_chroma_corrected_black_and_white].
+pub fn luma<N: Clone + Into<f64>>(img: &Img<RGB<N>>) -> Img<f64> {
+    let luma = img.iter().map(|rgb| {
+        rgb.clone()
+            .convert_with(Into::into)
+            .to_chroma_corrected_black_and_white()
+    });
+    Img::new(luma, img.width()).unwrap()
+}
+
+/// the [Sobel](https://en.wikipedia.org/wiki/Sobel_operator) gradient `(dx, dy)` at each pixel, scaled so a
+/// hard step between two levels has a magnitude of their difference. the edges of the image are extended outward.
+/// ```
+/// # use dither::prelude::*;
+/// # use dither::edges;
+/// let step = Img::new((0..9).map(|i| if i % 3 == 0 { 0. } else { 100. }), 3).unwrap();
+/// assert_eq!(edges::sobel(&step)[(1, 1)], (100., 0.));
+/// ```
+pub fn sobel(luma: &Img<f64>) -> Img<(f64, f64)> {
+    let gradient = pixels(luma).map(|(x, y)| {
+        let at = |dx: i64, dy: i64| clamped(luma, x, y, dx, dy);
+        let dx = (at(1, -1) + 2. * at(1, 0) + at(1, 1)) - (at(-1, -1) + 2. * at(-1, 0) + at(-1, 1));
+        let dy = (at(-1, 1) + 2. * at(0, 1) + at(1, 1)) - (at(-1, -1) + 2. * at(0, -1) + at(1, -1));
+        (dx / 4., dy / 4.)
+    });
+    Img::new(gradient.collect::<Vec<_>>(), luma.width()).unwrap()
+}
+
+/// find the edges in `luma`: pixels whose gradient is at least `threshold`, in the same units as the luminance
+/// (0 to 255), widened to lines `thickness` pixels thick. a `thickness` of 0 finds no edges.
+pub fn detect(luma: &Img<f64>, detector: Detector, threshold: f64, thickness: u32) -> Img<bool> {
+    let (width, height) = luma.size();
+    let magnitude = |(dx, dy): (f64, f64)| f64::hypot(dx, dy);
+    let lines = match detector {
+        Detector::Sobel => sobel(luma).convert_with(|g| magnitude(g) >= threshold),
+        Detector::Canny => {
+            let gradient = sobel(&blur(luma));
+            // non-maximum suppression: keep each pixel only if it's the ridge of the gradient across the edge.
+            let ridge: Vec<f64> = pixels(luma)
+                .map(|(x, y)| {
+                    let (dx, dy) = gradient[(x, y)];
+                    let m = magnitude((dx, dy));
+                    // the neighbor across the edge, rounding the gradient's direction to the nearest 45 degrees.
+                    let angle = f64::atan2(dy, dx).to_degrees().rem_euclid(180.);
+                    let (nx, ny) = match angle {
+                        a if !(22.5..157.5).contains(&a) => (1, 0),
+                        a if a < 67.5 => (1, 1),
+                        a if a < 112.5 => (0, 1),
+                        _ => (-1, 1),
+                    };
+                    let neighbor = |s: i64| {
+                        offset((x, y), (s * nx, s * ny), (width, height))
+                            .map_or(0., |at| magnitude(gradient[at]))
+                    };
+                    if m >= neighbor(1) && m > neighbor(-1) {
+                        m
+                    } else {
+                        0.
+                    }
+                })
+                .collect();
+            let ridge = Img::from_vec(ridge, width).unwrap();
+            // hysteresis: follow edges from strong pixels through weak ones.
+            let mut lines = ridge.clone().convert_with(|_| false);
+            let mut stack: Vec<(u32, u32)> =
+                pixels(luma).filter(|&at| ridge[at] >= threshold).collect();
+            while let Some(at) = stack.pop() {
+                if lines[at] {
+                    continue;
+                }
+                lines[at] = true;
+                for (dx, dy) in NEIGHBORS {
+                    if let Some(n) = offset(at, (dx, dy), (width, height)) {
+                        if !lines[n] && ridge[n] >= threshold / 2. {
+                            stack.push(n);
+                        }
+                    }
+                }
+            }
+            lines
+        }
+    };
+    widen(&lines, thickness)
+}
+
+/// draw `color` over every pixel of `img` that's on one of the `edges`.
+/// # Panics
+/// if `img` and `edges` are different sizes.
+pub fn overlay<P: Clone>(img: &mut Img<P>, edges: &Img<bool>, color: P) {
+    assert_eq!(
+        img.size(),
+        edges.size(),
+        "edges must be the same size as the image"
+    );
+    for (p, &edge) in img.iter_mut().zip(edges.iter()) {
+        if edge {
+            *p = color.clone();
+        }
+    }
+}
+
+const NEIGHBORS: [(i64, i64); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// every position in `img`, in row-major order.
+fn pixels<P>(img: &Img<P>) -> impl Iterator<Item = (u32, u32)> {
+    let (width, height) = img.size();
+    (0..height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+}
+
+/// the position `(dx, dy)` away from `(x, y)`, if it's in bounds.
+fn offset(
+    (x, y): (u32, u32),
+    (dx, dy): (i64, i64),
+    (width, height): (u32, u32),
+) -> Option<(u32, u32)> {
+    let (x, y) = (i64::from(x) + dx, i64::from(y) + dy);
+    if (0..i64::from(width)).contains(&x) && (0..i64::from(height)).contains(&y) {
+        Some((x as u32, y as u32))
+    } else {
+        None
+    }
+}
+
+/// the value `(dx, dy)` away from `(x, y)`, clamped to the edges of the image.
+fn clamped(img: &Img<f64>, x: u32, y: u32, dx: i64, dy: i64) -> f64 {
+    let (width, height) = img.size();
+    let x = (i64::from(x) + dx).clamp(0, i64::from(width) - 1) as u32;
+    let y = (i64::from(y) + dy).clamp(0, i64::from(height) - 1) as u32;
+    img[(x, y)]
+}
+
+/// a 3x3 binomial blur, to keep Canny from tracing noise.
+fn blur(img: &Img<f64>) -> Img<f64> {
+    let blurred = pixels(img).map(|(x, y)| {
+        let mut sum = 0.;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let weight = [1., 2., 1.][(dx + 1) as usize] * [1., 2., 1.][(dy + 1) as usize];
+                sum += weight * clamped(img, x, y, dx, dy);
+            }
+        }
+        sum / 16.
+    });
+    Img::new(blurred.collect::<Vec<_>>(), img.width()).unwrap()
+}
+
+/// widen every line to `thickness` pixels.
+fn widen(lines: &Img<bool>, thickness: u32) -> Img<bool> {
+    let (width, height) = lines.size();
+    let (before, after) = (
+        i64::from(thickness.saturating_sub(1) / 2),
+        i64::from(thickness / 2),
+    );
+    let mut wide = lines.clone().convert_with(|_| false);
+    for at in pixels(lines).filter(|&at| lines[at] && thickness > 0) {
+        for dy in -before..=after {
+            for dx in -before..=after {
+                if let Some(n) = offset(at, (dx, dy), (width, height)) {
+                    wide[n] = true;
+                }
+            }
+        }
+    }
+    wide
+}
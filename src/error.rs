@@ -11,10 +11,49 @@ pub enum Error {
     Input(IOError),
     /// A bit depth that's not in the [range][std::ops::Range] `0..8`
     BadBitDepth(u8),
+    /// An audio bit depth that's not in the [range][std::ops::RangeInclusive] `1..=24`
+    BadSampleDepth(u8),
     /// An error creating a [color::Mode]
     Color(color::Error),
     /// The user has specified both [color::Mode::CustomPalette] and the bit depth [Opt]
     CustomPaletteIncompatibleWithDepth,
+    /// The user has specified the `--smart` [Opt] without a [color::Mode::Palette]
+    SmartRequiresPalette,
+    /// The user has specified the `--extract` [Opt] without [color::Mode::Auto]
+    ExtractRequiresAuto,
+    /// The user has specified the `--subset` [Opt] without a [color::Mode::Palette]
+    SubsetRequiresPalette,
+    /// The user has specified the `--save-palette` [Opt] without a [color::Mode::Palette]
+    SavePaletteRequiresPalette,
+    /// The user has specified the `--distance` [Opt] without a [color::Mode::Palette]
+    DistanceRequiresPalette,
+    /// The user has specified the `--space` [Opt] without a [color::Mode::Palette]
+    SpaceRequiresPalette,
+    /// The user has specified the `--lut` [Opt] without a [color::Mode::Palette]
+    LutRequiresPalette,
+    /// The user has specified both [color::Mode::Palette] and the `--fixed` [Opt]
+    FixedIncompatibleWithPalette,
+    /// The user has specified the `--subpixel` [Opt] without [color::Mode::Color]
+    SubpixelRequiresColor,
+    /// The image is too wide to [split][color::subpixel::split] into sub-pixels: three to a pixel would be more than [u32::MAX] wide
+    SubpixelTooWide(u32),
+    /// The user has specified both the `--frames` and `--fixed` [Opt]s
+    FramesIncompatibleWithFixed,
+    /// The user has specified both the `--sequence` and `--fixed` [Opt]s
+    SequenceIncompatibleWithFixed,
+    /// The user has specified both the `--sequence` and `--frames` [Opt]s
+    SequenceIncompatibleWithFrames,
+    /// The user has specified the `--packed` [Opt] without [color::Mode::BlackAndWhite] or [color::Mode::Color]
+    PackedRequiresBitDepthMode,
+    /// The user has specified the `--fixed` [Opt] with an algorithm that doesn't have an integer error-diffusion kernel
+    /// whose weights add up to at most its divisor
+    FixedRequiresKernel(String),
+    /// The user has specified an `--edges` threshold that isn't a positive number
+    BadEdgeThreshold(f64),
+    /// The user has specified an `--edge-color` that isn't in the palette [color::Mode::Palette]
+    EdgeColorNotInPalette(RGB<u8>),
+    /// A dither was stopped early through its [Cancel][crate::ditherer::Cancel] token
+    Cancelled,
 }
 
 /// Result type for [Error]
@@ -60,10 +99,76 @@ impl std::fmt::Display for Error {
                 "configuration error: bit depth must be between 1 and 7, but was {}",
                 n
             ),
+            Error::BadSampleDepth(n) => write!(
+                f,
+                "configuration error: audio bit depth must be between 1 and 24, but was {}",
+                n
+            ),
             Error::Color(err) => write!(f, "configuration error for: {}", err),
             Error::CustomPaletteIncompatibleWithDepth => f.write_str(
                 "error: the custom palette --color option is incompatible with the --depth option",
             ),
+            Error::SmartRequiresPalette => f.write_str(
+                "error: the --smart option requires a palette --color option, like --color=cga",
+            ),
+            Error::FixedIncompatibleWithPalette => f.write_str(
+                "error: the --fixed option is incompatible with palette --color options",
+            ),
+            Error::ExtractRequiresAuto => f.write_str(
+                "error: the --extract option requires an extracted palette --color, like --color=auto:16",
+            ),
+            Error::SubsetRequiresPalette => f.write_str(
+                "error: the --subset option requires a palette --color, like --color=cga",
+            ),
+            Error::SavePaletteRequiresPalette => f.write_str(
+                "error: the --save-palette option requires a palette --color, like --color=cga",
+            ),
+            Error::DistanceRequiresPalette => f.write_str(
+                "error: the --distance option requires a palette --color, like --color=cga",
+            ),
+            Error::SpaceRequiresPalette => f.write_str(
+                "error: the --space option requires a palette --color, like --color=cga",
+            ),
+            Error::LutRequiresPalette => f.write_str(
+                "error: the --lut option requires a palette --color, like --color=cga",
+            ),
+            Error::SubpixelRequiresColor => {
+                f.write_str("error: the --subpixel option requires --color=color")
+            }
+            Error::SubpixelTooWide(width) => write!(
+                f,
+                "error: the image is {} pixels wide, but splitting it into sub-pixels supports at most {}",
+                width,
+                u32::MAX / 3
+            ),
+            Error::FramesIncompatibleWithFixed => {
+                f.write_str("error: the --frames option is incompatible with the --fixed option")
+            }
+            Error::SequenceIncompatibleWithFixed => {
+                f.write_str("error: the --sequence option is incompatible with the --fixed option")
+            }
+            Error::SequenceIncompatibleWithFrames => {
+                f.write_str("error: the --sequence option is incompatible with the --frames option")
+            }
+            Error::PackedRequiresBitDepthMode => f.write_str(
+                "error: the --packed option requires --color=bw or --color=color",
+            ),
+            Error::FixedRequiresKernel(name) => write!(
+                f,
+                "error: the --fixed option requires an error-diffusion kernel with integer weights that add up to at most its divisor, but {} doesn't have one",
+                name
+            ),
+            Error::BadEdgeThreshold(threshold) => write!(
+                f,
+                "error: the --edges threshold must be a positive number, not {}",
+                threshold
+            ),
+            Error::EdgeColorNotInPalette(color) => write!(
+                f,
+                "error: the --edge-color {:06x} isn't in the palette; pick one of the palette's colors",
+                color
+            ),
+            Error::Cancelled => f.write_str("error: dithering was cancelled"),
         }
     }
 }
@@ -3,6 +3,7 @@ use super::{Error, Result};
 use std::ops::{Index, IndexMut};
 use std::path::Path;
 /// Image as a flat buffer of pixels; accessible by (x, y) [Index]
+/// The width and height are each limited to [u32::MAX], but the number of pixels isn't.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Img<P> {
     buf: Vec<P>,
@@ -10,10 +11,22 @@ pub struct Img<P> {
 }
 
 impl<P> Img<P> {
-    /// create an Img<P> from a buf and width. fails if `buf.len() % buf.width() != 0`
+    /// create an Img<P> from a buf and width. fails if `buf.len() % buf.width() != 0`, or the height would be larger than [u32::MAX]
     pub fn new(buf: impl IntoIterator<Item = P>, width: u32) -> Option<Self> {
-        let buf: Vec<P> = buf.into_iter().collect();
-        if width == 0 || buf.len() % width as usize != 0 {
+        Self::from_vec(buf.into_iter().collect(), width)
+    }
+
+    /// create an Img<P> from a vec and width without copying it. fails if `buf.len() % buf.width() != 0`, or the height would be larger than [u32::MAX]
+    /// ```
+    /// # use dither::prelude::*;
+    /// assert_eq!(Img::from_vec(vec![2, 4, 6, 8], 2).unwrap().height(), 2);
+    /// assert_eq!(Img::from_vec(vec![2, 4, 6], 2), None);
+    /// ```
+    pub fn from_vec(buf: Vec<P>, width: u32) -> Option<Self> {
+        if width == 0
+            || !buf.len().is_multiple_of(width as usize)
+            || buf.len() / width as usize > u32::MAX as usize
+        {
             None
         } else {
             Some(Img { buf, width })
@@ -27,6 +40,8 @@ impl<P> Img<P> {
     ///     Img::new(vec![2, 4, 6, 8], 2).unwrap()
     /// );
     /// ```
+    /// # Safety
+    /// the caller must ensure that `width != 0`, `buf.len() % width == 0`, and `buf.len() / width <= u32::MAX`; otherwise indexing will misbehave.
     pub const unsafe fn from_raw_buf(buf: Vec<P>, width: u32) -> Self {
         Img { buf, width }
     }
@@ -63,7 +78,8 @@ impl<P> Img<P> {
     /// assert_eq!(Img::new(0..12, 3).unwrap().height(), 4);
     /// ```
     pub fn height(&self) -> u32 {
-        self.len() as u32 / self.width
+        // can't truncate: construction checks that the height fits in a u32.
+        (self.len() / self.width as usize) as u32
     }
     /// map a function on P across the image buffer, converting an `Img<P>` to an `Img<Q>`
     ///
@@ -73,7 +89,7 @@ impl<P> Img<P> {
     /// let doubled: Img<u16> = Img::new(vec![2, 4, 6, 8], 2).unwrap();
     /// assert_eq!(img.convert_with(|x| u16::from(x*2)), doubled);
     /// ```
-    pub fn convert_with<Q>(self, convert: impl Fn(P) -> Q) -> Img<Q> {
+    pub fn convert_with<Q>(self, convert: impl FnMut(P) -> Q) -> Img<Q> {
         let Img { buf, width } = self;
         Img {
             buf: buf.into_iter().map(convert).collect(),
@@ -82,9 +98,9 @@ impl<P> Img<P> {
     }
     #[inline]
     fn idx(&self, (x, y): (u32, u32)) -> usize {
-        ((y * self.width) + x) as usize
+        (y as usize * self.width as usize) + x as usize
     }
-    /// the length of the image, in _pixels_. equal to [Img::width()]*[Img::height()]
+    /// the length of the image, in _pixels_. equal to [Img::width()]*[Img::height()], which may be more than [u32::MAX]
     pub fn len(&self) -> usize {
         self.buf.len()
     }
@@ -99,7 +115,7 @@ impl<P> Img<P> {
     }
     /// Returns a pair `(width, height)`.
     pub fn size(&self) -> (u32, u32) {
-        (self.width, self.len() as u32 / self.width as u32)
+        (self.width, self.height())
     }
 }
 
@@ -111,7 +127,7 @@ impl<N: From<u8>> Img<RGB<N>> {
     /// assert_eq!(img.size(), (480, 320));
     /// ```
     pub fn load(path: impl AsRef<Path>) -> Result<Self> {
-        match image::open(&path).and_then(|img| Ok(img.to_rgb8())) {
+        match image::open(&path).map(|img| img.to_rgb8()) {
             Err(err) => Err(Error::input(err, path.as_ref())),
             Ok(img) => Ok(Img {
                 buf: img.pixels().map(|p| RGB::from(p.0)).collect(),
@@ -176,7 +192,7 @@ impl<'a, P> IntoIterator for &'a Img<P> {
     type Item = &'a P;
     type IntoIter = std::slice::Iter<'a, P>;
     fn into_iter(self) -> Self::IntoIter {
-        (&self.buf).iter()
+        self.buf.iter()
     }
 }
 
@@ -184,6 +200,6 @@ impl<'a, P> IntoIterator for &'a mut Img<P> {
     type Item = &'a mut P;
     type IntoIter = std::slice::IterMut<'a, P>;
     fn into_iter(self) -> Self::IntoIter {
-        (&mut self.buf).iter_mut()
+        self.buf.iter_mut()
     }
 }
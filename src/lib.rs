@@ -5,12 +5,16 @@
 //! Inspired by: <http://www.tannerhelland.com/4660/dithering-eleven-algorithms-source-code/>
 //! and the game "Return of the Obra Dinn"
 
+pub mod cli;
 pub mod color;
 pub mod ditherer;
+pub mod edges;
 mod error;
 mod img;
 mod opts;
+pub mod pixel;
 pub mod prelude;
+pub mod quantize;
 pub use self::error::Error;
 pub use self::error::Result;
 
@@ -29,27 +33,11 @@ mod tests;
 ///
 /// ```
 pub fn create_quantize_n_bits_func(n: u8) -> Result<impl Fn(f64) -> (f64, f64)> {
-    if n == 0 || n > 7 {
-        Err(Error::BadBitDepth(n))
-    } else {
-        Ok(move |x: f64| {
-            let step_size = 255. / f64::from(n);
-
-            let floor = f64::floor(x / step_size) * step_size;
-            let floor_rem = x - floor;
-
-            let ceil = f64::ceil(x / step_size) * step_size;
-            let ceil_rem = ceil - x;
-
-            if floor_rem < ceil_rem {
-                let quot = f64::max(floor, 0.0);
-                (quot, floor_rem)
-            } else {
-                let quot = f64::min(255.0, ceil);
-                (quot, -ceil_rem)
-            }
-        })
-    }
+    let depth = quantize::BitDepth::new(n)?;
+    Ok(move |x: f64| {
+        let quot = depth.level(x);
+        (quot, x - quot)
+    })
 }
 
 /// clamp a f64 to the closest u8, rounding non-integers.
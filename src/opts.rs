@@ -30,7 +30,7 @@ pub struct Opt {
     #[clap(name = "output")]
     pub output: Option<PathBuf>,
 
-    /// Ditherering algorithm to use. Options are
+    /// Ditherering algorithm to use, from the [registry][crate::ditherer::registry]. Options are
     /// - "floyd" (default)
     /// - "atkinson"
     /// - "stucki",
@@ -38,8 +38,9 @@ pub struct Opt {
     /// - "jarvis"
     /// - "sierra3"
     ///
+    /// and any others that have been registered. Algorithms with parameters are selected as "name:parameter=value,parameter=value".
     #[clap(short = 'd', long = "dither", default_value = "floyd")]
-    pub ditherer: Ditherer<'static>,
+    pub ditherer: ditherer::registry::Selected,
 
     /// Color mode to use.
     /// Options are
@@ -47,23 +48,153 @@ pub struct Opt {
     /// - color => color mode with the specified bit depth.
     /// - cga => load the cga palette. equivalent to "cga.plt".
     /// - crayon => load the crayon palette. equivalent to "crayon.plt"
+    /// - $PALETTE => a built-in hardware or art palette: ega, vga, gameboy, pico8, nes, c64, zx_spectrum, apple2, msx,
+    ///   amstrad_cpc, mac16, win16, web_safe, teletext, epaper3_red, epaper3_yellow, or epaper7.
     /// - $COLOR => single-color mode. options are
     /// - $FILENAME" => load palette from file, listed as line-separated RGB values. see "cga.plt" and the readme for more information on palette files.
+    ///   GIMP .gpl, JASC-PAL .pal, Paint.NET .txt, Adobe .aco and .ase, and .hex palettes work too.
+    /// - auto:$N => extract a palette of $N colors from the input image. see `--extract`.
     #[clap(short = 'c', long = "color", default_value = "bw")]
     pub color_mode: color::Mode,
+
+    /// The algorithm to extract a palette with, for `--color=auto:N`. Options are
+    /// - "median-cut": Heckbert's median cut.
+    /// - "kmeans": k-means++; the best colors, and the slowest.
+    /// - "octree": an octree quantizer.
+    /// - "wu" (default): Wu's quantizer; fast, and nearly as good as k-means.
+    ///
+    /// See [extract][crate::color::palette::extract]
+    #[clap(long = "extract", value_enum, value_name = "METHOD")]
+    pub extract: Option<color::palette::extract::Method>,
+
+    /// Dither with only the best K colors of a palette `--color`, for hardware with a fixed master palette but a
+    /// smaller budget per image; i.e, `--color=cga --subset=4`. Must be at least 2. See [subset][crate::color::palette::subset]
+    #[clap(long = "subset", value_name = "K")]
+    pub subset: Option<std::num::NonZeroUsize>,
+
+    /// Save the palette the image was dithered with to this path, to take it back into a paint program; i.e, the
+    /// colors picked by `--color=auto:N` or `--subset`. The format comes from the extension: .plt (default), GIMP
+    /// .gpl, JASC-PAL .pal, Paint.NET .txt, Adobe .aco or .ase, or .hex. Requires a palette `--color`.
+    /// See [format][crate::color::palette::format]
+    #[clap(long = "save-palette", value_name = "PATH")]
+    pub save_palette: Option<PathBuf>,
+
+    /// "Smart dither": quantize flat regions to a solid color instead of dithering them. Pixels within the given
+    /// tolerance of a palette color or all of their neighbors count as flat. Requires a palette `--color`.
+    /// See [palette::quantize_flat][crate::color::palette::quantize_flat]
+    #[clap(long = "smart", value_name = "TOLERANCE")]
+    pub flat_tolerance: Option<f64>,
+
+    /// How to match pixels to a palette `--color`. Options are
+    /// - "manhattan" (default in sRGB): the sum of the differences of the RGB channels.
+    /// - "euclidean": straight-line RGB distance.
+    /// - "redmean": RGB, weighted by how red the colors are; a cheap approximation of perception.
+    /// - "de76": CIELAB ΔE 1976.
+    /// - "de2000": CIEDE2000 ΔE; the most accurate, and the slowest.
+    /// - "oklab": straight-line Oklab distance.
+    ///
+    /// Requires a palette `--color`. See [ColorDistance][crate::color::distance::ColorDistance]
+    #[clap(long = "distance", value_enum, value_name = "METRIC")]
+    pub distance: Option<color::distance::ColorDistance>,
+
+    /// The color space to spread error in, for a palette `--color`. Options are
+    /// - "srgb" (default)
+    /// - "lab": CIELAB.
+    /// - "oklab"
+    ///
+    /// Spreading error in a perceptual space avoids the hue shifts of sRGB. Unless `--distance` is given,
+    /// colors are matched by straight-line distance in the space. See [space][crate::color::space]
+    #[clap(long = "space", value_enum, value_name = "SPACE")]
+    pub space: Option<color::space::Space>,
+
+    /// Match pixels to a palette `--color` with a precomputed 64x64x64 lookup table, instead of searching the
+    /// palette for each one. Much faster for large palettes, but approximate: each pixel is matched as the center
+    /// of its 4x4x4 block of colors. Requires a palette `--color`. See [Search::Lut][crate::color::palette::Search::Lut]
+    #[clap(long = "lut")]
+    pub lut: bool,
+
+    /// Dither with fixed-point integer math instead of floating point, for bit-exact output on every platform.
+    /// Incompatible with palette `--color` options. See [fixed][crate::ditherer::fixed]
+    #[clap(long = "fixed")]
+    pub fixed: bool,
+
+    /// Floating-point precision to dither in. Options are
+    /// - "f32" (default): half the memory of f64, and faster.
+    /// - "f64"
+    #[clap(long = "precision", value_enum, default_value = "f32")]
+    pub precision: Precision,
+
+    /// Dither each pixel's red, green and blue sub-pixels as separate horizontal samples, for LCD panels.
+    /// The value is the panel's stripe order: "rgb" or "bgr". Requires `--color=color`. See [subpixel][crate::color::subpixel]
+    #[clap(long = "subpixel", value_enum, value_name = "ORDER")]
+    pub subpixel: Option<color::subpixel::Stripe>,
+
+    /// Dither into this many frames whose average over time approximates the image, for displays that fake
+    /// grayscale by switching pixels on and off each refresh. Frames are saved as numbered images:
+    /// `out.png` becomes `out_0.png`, `out_1.png`, and so on. See [temporal][crate::ditherer::temporal]
+    #[clap(long = "frames", value_name = "N")]
+    pub frames: Option<std::num::NonZeroUsize>,
+
+    /// Write the frames to the output path as a single packed stream of levels instead of as images.
+    /// Requires `--color=bw` or `--color=color`. See [temporal::pack][crate::ditherer::temporal::pack]
+    #[clap(long = "packed")]
+    pub packed: bool,
+
+    /// Dither a sequence of frames, like an animation: `input` is a directory of images, dithered in name order,
+    /// and `output` a directory to save them to under the same names. Pixels whose color changes by at most
+    /// the given tolerance (manhattan distance in RGB; default 0) keep their output from the previous frame,
+    /// so static regions don't shimmer. Incompatible with `--fixed` and `--frames`. See [sequence][crate::ditherer::sequence]
+    #[clap(long = "sequence", value_name = "TOLERANCE", num_args = 0..=1, default_missing_value = "0")]
+    pub sequence: Option<f64>,
+
+    /// Draw the edges of the input as solid lines over the output, for an ink-outlined look. The value is the
+    /// threshold: how sharp a change in brightness, from 0 to 255, counts as an edge. Must be above 0. See [edges][crate::edges]
+    #[clap(long = "edges", value_name = "THRESHOLD")]
+    pub edges: Option<f64>,
+
+    /// How to find `--edges`. Options are
+    /// - "canny" (default): thin, connected lines.
+    /// - "sobel": every pixel on a steep enough gradient; thicker and softer.
+    #[clap(long = "edge-detector", value_enum, default_value = "canny")]
+    pub edge_detector: crate::edges::Detector,
+
+    /// The thickness of the `--edges` lines, in pixels.
+    #[clap(long = "edge-thickness", default_value = "1", value_name = "PIXELS")]
+    pub edge_thickness: u32,
+
+    /// The color of the `--edges` lines, as six hexadecimal digits: i.e, `000000` for black.
+    /// With a palette `--color`, it must be one of the palette's colors.
+    #[clap(long = "edge-color", default_value = "000000", value_name = "RGB")]
+    pub edge_color: RGB<u8>,
+
+    /// Number of threads to dither with. Default is the number of available cores. The output doesn't depend on it.
+    /// See [Ditherer::dither_parallel]
+    #[clap(short = 'j', long = "threads")]
+    pub threads: Option<usize>,
+}
+
+/// The floating-point working representation of the image while dithering. Corresponds to [Opt] `--precision`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Precision {
+    /// dither an [Img]<[RGB]<[f32]>>.
+    #[default]
+    F32,
+    /// dither an [Img]<[RGB]<[f64]>>.
+    F64,
 }
 
 impl Opt {
     /// the [canonicalized][std::fs::canonicalize] input path
-    pub fn input_path<'a>(&'a self) -> Result<PathBuf> {
+    pub fn input_path(&self) -> Result<PathBuf> {
         match self.input.canonicalize() {
-            Err(err) => return Err(Error::Input(IOError::new(err, &self.input))),
+            Err(err) => Err(Error::Input(IOError::new(err, &self.input))),
             Ok(abs_path) => Ok(abs_path),
         }
     }
     /// the actual output path. if opts.output exists, this is that, otherwise, this is
-    /// `"{base}_dithered_{dither}_{color}_{depth}.png"`,
-    /// where base is the [canonicalized][std::fs::canonicalize] input path, stripped of it's extension.
+    /// `"{base}_dithered_{dither}_{color}_{depth}.png"`, without the `.png` for a `--sequence` directory,
+    /// where base is the [canonicalized][std::fs::canonicalize] input path, stripped of it's extension,
+    /// and dither is the ditherer's [file name][crate::ditherer::registry::Selected::file_name].
     /// `$dither bunny.png --color=color --dither=atkinson --depth=2` will save to `bunny_atkinson_c_2.png`
     ///
     /// ```
@@ -78,7 +209,7 @@ impl Opt {
     ///
     pub fn output_path<'a>(&'a self) -> Result<Cow<'a, Path>> {
         if let Some(path) = &self.output {
-            return Ok(Cow::Borrowed(&path));
+            return Ok(Cow::Borrowed(path));
         }
 
         let abs_path = match self.input.canonicalize() {
@@ -86,11 +217,12 @@ impl Opt {
             Ok(abs_path) => abs_path,
         };
         let path = format!(
-            "{base}_dithered_{dither}_{color}_{depth}.png",
+            "{base}_dithered_{dither}_{color}_{depth}{extension}",
             base = abs_path.file_stem().unwrap_or_default().to_string_lossy(),
-            dither = self.ditherer,
+            dither = self.ditherer.file_name(),
             color = self.color_mode,
             depth = self.bit_depth,
+            extension = if self.sequence.is_some() { "" } else { ".png" },
         );
         Ok(Cow::Owned(PathBuf::from(path)))
     }
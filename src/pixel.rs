@@ -0,0 +1,150 @@
+//! Pixels with any number of channels. The ditherers work on any [Pixel], so the same kernels dither grayscale
+//! (luma) images, [RGB], [RGBA] and [CMYK] images, and multispectral data as `[f32; N]`.
+//! ```
+//! # use dither::prelude::*;
+//! # use dither::pixel::Pixel;
+//! # use dither::color::CMYK;
+//! let quantize = dither::create_quantize_n_bits_func(1).unwrap();
+//! let separations = Img::new(vec![CMYK(10., 200., 128., 60.); 16], 4).unwrap();
+//! let got = Ditherer::default().dither(separations, dither::quantize::PerChannel(&quantize));
+//! assert!(got.iter().all(|p| p.channels().all(|c| c == 0. || c == 255.)));
+//! ```
+use crate::color::{CMYK, RGB, RGBA};
+use std::ops::Add;
+
+/// A single channel of a [Pixel]: a scalar that can be summed, and scaled by a kernel weight.
+/// Implemented for [f64] and the more compact [f32], [i32], and [i16].
+/// ```
+/// # use dither::pixel::Channel;
+/// assert_eq!(100_i16.scale(7., 16.), 44);
+/// ```
+pub trait Channel: Add<Output = Self> + Copy + Default {
+    /// `self * mul / div`. integer types round to the nearest integer.
+    fn scale(self, mul: f64, div: f64) -> Self;
+}
+
+impl Channel for f64 {
+    fn scale(self, mul: f64, div: f64) -> Self {
+        self * mul / div
+    }
+}
+
+impl Channel for f32 {
+    fn scale(self, mul: f64, div: f64) -> Self {
+        self * mul as f32 / div as f32
+    }
+}
+
+impl Channel for i32 {
+    fn scale(self, mul: f64, div: f64) -> Self {
+        (f64::from(self) * mul / div).round() as i32
+    }
+}
+
+impl Channel for i16 {
+    fn scale(self, mul: f64, div: f64) -> Self {
+        (f64::from(self) * mul / div).round() as i16
+    }
+}
+
+/// A pixel: a fixed number of [Channel]s, which error can be diffused across channel by channel.
+/// A bare [Channel] is a one-channel (luma) pixel.
+/// ```
+/// # use dither::prelude::*;
+/// # use dither::pixel::Pixel;
+/// assert_eq!(RGB::<f32>::CHANNELS, 3);
+/// assert_eq!(RGB(16_f32, 32., 48.).scale(7., 16.), RGB(7., 14., 21.));
+/// assert_eq!([1., 2., 3., 4., 5.].plus(&[1.; 5]), [2., 3., 4., 5., 6.]);
+/// ```
+pub trait Pixel: Clone {
+    type Channel: Channel;
+    /// the number of channels.
+    const CHANNELS: usize;
+
+    /// build a pixel from a function of each channel's index.
+    fn from_channels(channel: impl FnMut(usize) -> Self::Channel) -> Self;
+    /// the channel at index `i`.
+    /// # Panics
+    /// if `i >= CHANNELS`.
+    fn channel(&self, i: usize) -> Self::Channel;
+
+    /// iterate over the channels in order.
+    fn channels(&self) -> impl Iterator<Item = Self::Channel> + '_ {
+        (0..Self::CHANNELS).map(|i| self.channel(i))
+    }
+    /// map a function across every channel.
+    fn map(&self, mut f: impl FnMut(Self::Channel) -> Self::Channel) -> Self {
+        Self::from_channels(|i| f(self.channel(i)))
+    }
+    /// the pixel with every channel zeroed; i.e, no error.
+    fn zero() -> Self {
+        Self::from_channels(|_| Self::Channel::default())
+    }
+    /// channel-by-channel sum.
+    fn plus(&self, other: &Self) -> Self {
+        Self::from_channels(|i| self.channel(i) + other.channel(i))
+    }
+    /// `self * mul / div`, channel by channel.
+    fn scale(&self, mul: f64, div: f64) -> Self {
+        self.map(|c| c.scale(mul, div))
+    }
+}
+
+impl<C: Channel> Pixel for C {
+    type Channel = C;
+    const CHANNELS: usize = 1;
+    fn from_channels(mut channel: impl FnMut(usize) -> C) -> Self {
+        channel(0)
+    }
+    fn channel(&self, i: usize) -> C {
+        assert_eq!(i, 0, "channel out of range");
+        *self
+    }
+}
+
+impl<C: Channel> Pixel for RGB<C> {
+    type Channel = C;
+    const CHANNELS: usize = 3;
+    fn from_channels(mut channel: impl FnMut(usize) -> C) -> Self {
+        RGB(channel(0), channel(1), channel(2))
+    }
+    fn channel(&self, i: usize) -> C {
+        let RGB(r, g, b) = self;
+        [*r, *g, *b][i]
+    }
+}
+
+impl<C: Channel> Pixel for RGBA<C> {
+    type Channel = C;
+    const CHANNELS: usize = 4;
+    fn from_channels(mut channel: impl FnMut(usize) -> C) -> Self {
+        RGBA(channel(0), channel(1), channel(2), channel(3))
+    }
+    fn channel(&self, i: usize) -> C {
+        let RGBA(r, g, b, a) = self;
+        [*r, *g, *b, *a][i]
+    }
+}
+
+impl<C: Channel> Pixel for CMYK<C> {
+    type Channel = C;
+    const CHANNELS: usize = 4;
+    fn from_channels(mut channel: impl FnMut(usize) -> C) -> Self {
+        CMYK(channel(0), channel(1), channel(2), channel(3))
+    }
+    fn channel(&self, i: usize) -> C {
+        let CMYK(c, m, y, k) = self;
+        [*c, *m, *y, *k][i]
+    }
+}
+
+impl<C: Channel, const N: usize> Pixel for [C; N] {
+    type Channel = C;
+    const CHANNELS: usize = N;
+    fn from_channels(channel: impl FnMut(usize) -> C) -> Self {
+        std::array::from_fn(channel)
+    }
+    fn channel(&self, i: usize) -> C {
+        self[i]
+    }
+}
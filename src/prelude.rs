@@ -6,5 +6,6 @@ pub use self::{
     ditherer::{Dither, Ditherer},
     error::{Error, IOError, Result},
     img::Img,
-    opts::Opt,
+    opts::{Opt, Precision},
+    pixel::Pixel,
 };
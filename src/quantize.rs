@@ -0,0 +1,226 @@
+//! Quantizers: map a pixel to the nearest value the output can represent, and report the error to spread.
+//!
+//! Any `FnMut(P) -> (P, P)` closure returning `(nearest, error)` is a [Quantizer]; the types here cover the CLI's
+//! color modes, and compose: i.e, [PerChannel]`<`[BitDepth]`>` quantizes each channel of an [RGB] separately.
+//! ```
+//! # use dither::prelude::*;
+//! # use dither::quantize::{BitDepth, PerChannel, Quantizer};
+//! let mut two_bit = PerChannel(BitDepth::new(2).unwrap());
+//! assert_eq!(
+//!     two_bit.quantize(RGB(100., 130., 250.), (0, 0)),
+//!     (RGB(127.5, 127.5, 255.), RGB(-27.5, 2.5, -5.))
+//! );
+//! ```
+use crate::color::RGB;
+use crate::pixel::Pixel;
+use crate::{Error, Result};
+
+/// A quantizer for pixels of type `P`. Quantizers get the position `(x, y)` of each pixel, so they can be
+/// position-aware (i.e, ordered thresholds or per-tile palettes), and `&mut self`, so they can be stateful.
+pub trait Quantizer<P> {
+    /// the nearest value to `p` that the pixel at `(x, y)` can be.
+    fn nearest(&mut self, p: &P, at: (usize, usize)) -> P;
+    /// the error left over from representing `p` at `(x, y)` as `nearest`, to be spread to neighboring pixels.
+    fn error(&mut self, p: P, nearest: &P, at: (usize, usize)) -> P;
+    /// quantize the pixel `p` at `(x, y)`, returning the pair `(nearest, error)`.
+    fn quantize(&mut self, p: P, at: (usize, usize)) -> (P, P) {
+        let nearest = self.nearest(&p, at);
+        let error = self.error(p, &nearest, at);
+        (nearest, error)
+    }
+    /// whether the pixel at `(x, y)` is solid: it's quantized without spreading any error, whatever its value,
+    /// like [Flat][crate::color::palette::Flat]'s flat regions. quantizers that wrap another one can skip their
+    /// own error there. by default, no pixel is solid.
+    fn is_solid(&self, at: (usize, usize)) -> bool {
+        let _ = at;
+        false
+    }
+}
+
+/// a closure returning `(nearest, error)`. it ignores the position.
+impl<P: Clone, F: FnMut(P) -> (P, P)> Quantizer<P> for F {
+    fn nearest(&mut self, p: &P, _: (usize, usize)) -> P {
+        self(p.clone()).0
+    }
+    fn error(&mut self, p: P, _: &P, _: (usize, usize)) -> P {
+        self(p).1
+    }
+    fn quantize(&mut self, p: P, _: (usize, usize)) -> (P, P) {
+        self(p)
+    }
+}
+
+impl<'q, P> Quantizer<P> for &mut (dyn Quantizer<P> + 'q) {
+    fn nearest(&mut self, p: &P, at: (usize, usize)) -> P {
+        (**self).nearest(p, at)
+    }
+    fn error(&mut self, p: P, nearest: &P, at: (usize, usize)) -> P {
+        (**self).error(p, nearest, at)
+    }
+    fn quantize(&mut self, p: P, at: (usize, usize)) -> (P, P) {
+        (**self).quantize(p, at)
+    }
+    fn is_solid(&self, at: (usize, usize)) -> bool {
+        (**self).is_solid(at)
+    }
+}
+
+/// A [Quantizer] that can be cloned behind a trait object, so each thread of a parallel dither can have its own.
+/// Implemented for every [Clone] + [Send] quantizer.
+pub trait CloneQuantizer<P>: Quantizer<P> + Send {
+    fn clone_box<'q>(&self) -> Box<dyn CloneQuantizer<P> + 'q>
+    where
+        Self: 'q;
+}
+
+impl<P, Q: Quantizer<P> + Clone + Send> CloneQuantizer<P> for Q {
+    fn clone_box<'q>(&self) -> Box<dyn CloneQuantizer<P> + 'q>
+    where
+        Self: 'q,
+    {
+        Box::new(self.clone())
+    }
+}
+
+impl<'q, P: 'q> Clone for Box<dyn CloneQuantizer<P> + 'q> {
+    fn clone(&self) -> Self {
+        (**self).clone_box()
+    }
+}
+
+impl<'q, P> Quantizer<P> for Box<dyn CloneQuantizer<P> + 'q> {
+    fn nearest(&mut self, p: &P, at: (usize, usize)) -> P {
+        (**self).nearest(p, at)
+    }
+    fn error(&mut self, p: P, nearest: &P, at: (usize, usize)) -> P {
+        (**self).error(p, nearest, at)
+    }
+    fn quantize(&mut self, p: P, at: (usize, usize)) -> (P, P) {
+        (**self).quantize(p, at)
+    }
+    fn is_solid(&self, at: (usize, usize)) -> bool {
+        (**self).is_solid(at)
+    }
+}
+
+/// A floating-point channel that the built-in quantizers work in; they do their math in [f64].
+pub trait Float: Copy + Into<f64> {
+    fn from_f64(x: f64) -> Self;
+}
+
+impl Float for f64 {
+    fn from_f64(x: f64) -> Self {
+        x
+    }
+}
+
+impl Float for f32 {
+    fn from_f64(x: f64) -> Self {
+        x as f32
+    }
+}
+
+/// Quantize a channel to `n` bits; i.e, to the nearest of `n + 1` evenly spaced levels between 0 and 255.
+/// See [create_quantize_n_bits_func][crate::create_quantize_n_bits_func] and the `--depth` [Opt][crate::Opt].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitDepth(u8);
+
+impl BitDepth {
+    /// fails unless `n` is between 1 and 7.
+    pub fn new(n: u8) -> Result<Self> {
+        if n == 0 || n > 7 {
+            Err(Error::BadBitDepth(n))
+        } else {
+            Ok(BitDepth(n))
+        }
+    }
+
+    /// the nearest level to `x`. inputs outside of 0 to 255 get the nearest end.
+    pub fn level(self, x: f64) -> f64 {
+        let step_size = 255. / f64::from(self.0);
+
+        let floor = f64::floor(x / step_size) * step_size;
+        let ceil = f64::ceil(x / step_size) * step_size;
+        let nearest = if x - floor < ceil - x { floor } else { ceil };
+        nearest.clamp(0., 255.)
+    }
+}
+
+impl<N: Float> Quantizer<N> for BitDepth {
+    fn nearest(&mut self, p: &N, _: (usize, usize)) -> N {
+        N::from_f64(self.level((*p).into()))
+    }
+    fn error(&mut self, p: N, nearest: &N, _: (usize, usize)) -> N {
+        N::from_f64(p.into() - (*nearest).into())
+    }
+}
+
+/// Quantize each channel of a [Pixel] (i.e, an [RGB]) separately, with the same quantizer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerChannel<Q>(pub Q);
+
+impl<P: Pixel, Q: Quantizer<P::Channel>> Quantizer<P> for PerChannel<Q> {
+    fn nearest(&mut self, p: &P, at: (usize, usize)) -> P {
+        p.map(|c| self.0.nearest(&c, at))
+    }
+    fn error(&mut self, p: P, nearest: &P, at: (usize, usize)) -> P {
+        P::from_channels(|i| self.0.error(p.channel(i), &nearest.channel(i), at))
+    }
+    fn quantize(&mut self, p: P, at: (usize, usize)) -> (P, P) {
+        // stash the errors on the stack for the common layouts, so quantizing a pixel doesn't allocate.
+        let (mut stack, mut heap) = ([P::Channel::default(); 8], Vec::new());
+        let errors: &mut [P::Channel] = if P::CHANNELS <= stack.len() {
+            &mut stack
+        } else {
+            heap.resize(P::CHANNELS, P::Channel::default());
+            &mut heap
+        };
+        let nearest = P::from_channels(|i| {
+            let (nearest, error) = self.0.quantize(p.channel(i), at);
+            errors[i] = error;
+            nearest
+        });
+        (nearest, P::from_channels(|i| errors[i]))
+    }
+}
+
+/// Single-color mode: quantize the [brightness][RGB::to_chroma_corrected_black_and_white] of a pixel to a bit depth,
+/// then draw it in shades of one color on black. The error is the brightness error, spread equally across channels.
+/// See [Mode::SingleColor][crate::color::Mode::SingleColor]
+/// ```
+/// # use dither::prelude::*;
+/// # use dither::quantize::{BitDepth, Quantizer, SingleColor};
+/// # use dither::color::palette::cga;
+/// let mut red = SingleColor::new(cga::RED, BitDepth::new(1).unwrap());
+/// assert_eq!(red.quantize(RGB(200., 200., 200.), (0, 0)), (RGB(170., 0., 0.), RGB(-55., -55., -55.)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SingleColor {
+    color: RGB<u8>,
+    depth: BitDepth,
+}
+
+impl SingleColor {
+    pub fn new(color: RGB<u8>, depth: BitDepth) -> Self {
+        SingleColor { color, depth }
+    }
+
+    fn brightness<N: Float>(p: &RGB<N>) -> f64 {
+        p.clone()
+            .convert_with(Into::into)
+            .to_chroma_corrected_black_and_white()
+    }
+}
+
+impl<N: Float> Quantizer<RGB<N>> for SingleColor {
+    fn nearest(&mut self, p: &RGB<N>, _: (usize, usize)) -> RGB<N> {
+        let level = self.depth.level(Self::brightness(p));
+        self.color
+            .convert_with(|c| N::from_f64(f64::from(c) / 255. * level))
+    }
+    fn error(&mut self, p: RGB<N>, _: &RGB<N>, _: (usize, usize)) -> RGB<N> {
+        let brightness = Self::brightness(&p);
+        let error = N::from_f64(brightness - self.depth.level(brightness));
+        RGB(error, error, error)
+    }
+}
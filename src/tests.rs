@@ -35,3 +35,1047 @@ fn test_dither_no_op() {
     let img = Img::new(test_img_buf, 3).unwrap();
     assert_eq!(img, NO_OP_DITHER.dither(img.clone(), no_op));
 }
+
+#[test]
+fn test_dither_does_not_wrap_across_row_edges() {
+    // error from the end of one row used to spill onto the start of the next, and vice versa.
+    let img = Img::new(
+        vec![
+            100., 100., 100., 100., //
+            200., 60., 60., 200., //
+            90., 150., 30., 120.,
+        ],
+        4,
+    )
+    .unwrap();
+    let quantize = crate::create_quantize_n_bits_func(1).unwrap();
+    let want = Img::new(
+        vec![
+            0., 255., 0., 0., //
+            255., 0., 0., 255., //
+            0., 255., 0., 255.,
+        ],
+        4,
+    )
+    .unwrap();
+    assert_eq!(want, ditherer::FLOYD_STEINBERG.dither(img, quantize));
+}
+
+#[test]
+fn test_quantize_error_is_input_minus_level() {
+    use crate::quantize::{BitDepth, Quantizer};
+    // far below 0, the error used to be measured from the level below 0 before it was clamped, so it had the wrong sign.
+    let quantize = crate::create_quantize_n_bits_func(1).unwrap();
+    assert_eq!(quantize(-200.), (0., -200.));
+    assert_eq!(quantize(800.), (255., 545.));
+    for n in 1..=7 {
+        let mut depth = BitDepth::new(n).unwrap();
+        for x in [-300., -200., -1., 0., 100., 254., 255., 300., 800.] {
+            let (level, error) = depth.quantize(x, (0, 0));
+            assert!(
+                (0. ..=255.).contains(&level),
+                "{} bits: {} => {}",
+                n,
+                x,
+                level
+            );
+            assert_eq!(error, x - level, "{} bits: {}", n, x);
+        }
+    }
+}
+
+#[test]
+fn test_dither_parallel_matches_sequential() {
+    use crate::color::palette::{self, cga};
+    use crate::ditherer::*;
+    let img = load_test_image().convert_with(|rgb| rgb.convert_with(f64::from));
+    let quantize = palette::quantize(cga::ALL);
+    for ditherer in [
+        FLOYD_STEINBERG,
+        ATKINSON,
+        STUCKI,
+        BURKES,
+        JARVIS_JUDICE_NINKE,
+        SIERRA_3,
+    ] {
+        let want = ditherer.dither(img.clone(), quantize.clone());
+        for threads in [2, 3, 8] {
+            assert_eq!(
+                want,
+                ditherer.dither_parallel(img.clone(), quantize.clone(), threads),
+                "{} on {} threads",
+                ditherer,
+                threads
+            );
+        }
+    }
+}
+
+#[test]
+fn test_dither_strided_matches_dither() {
+    use crate::ditherer::STUCKI;
+    let quantize = crate::create_quantize_n_bits_func(2).unwrap();
+    let img = load_test_image()
+        .convert_with(|rgb| RGB::<f64>::from(rgb).to_chroma_corrected_black_and_white());
+    let (width, stride) = (img.width() as usize, img.width() as usize + 7);
+
+    let mut buf = Vec::new();
+    for row in img.clone().into_vec().chunks(width) {
+        buf.extend_from_slice(row);
+        buf.extend_from_slice(&[-1.; 7]);
+    }
+    // reuse dirty scratch space across frames.
+    let mut scratch = vec![1000.; STUCKI.scratch_len(width) + 3];
+    for _ in 0..2 {
+        let mut frame = buf.clone();
+        STUCKI.dither_strided(&mut frame, width, stride, &mut scratch, &quantize);
+        let got: Vec<f64> = frame
+            .chunks(stride)
+            .flat_map(|row| row[..width].to_vec())
+            .collect();
+        assert!(frame.chunks(stride).all(|row| row[width..] == [-1.; 7]));
+        assert_eq!(got, STUCKI.dither(img.clone(), &quantize).into_vec());
+    }
+}
+
+#[test]
+fn test_dither_fixed_agrees_with_float() {
+    use crate::ditherer::{fixed, optimize::perceptual_error, FLOYD_STEINBERG};
+    let img = load_test_image();
+    let width = img.width();
+    let gray = img.clone().convert_with(|rgb| f64::from(fixed::luma(rgb)));
+
+    let float = FLOYD_STEINBERG
+        .dither(gray.clone(), crate::create_quantize_n_bits_func(1).unwrap())
+        .convert_with(|p| f64::from(crate::clamp_f64_to_u8(p)));
+
+    let kernel = FLOYD_STEINBERG.to_fixed().unwrap();
+    let mut buf: Vec<u8> = img.into_iter().map(fixed::luma).collect();
+    let mut scratch = vec![0; kernel.scratch_len(width as usize, 1)];
+    kernel.dither(
+        &mut buf,
+        width as usize,
+        1,
+        &mut scratch,
+        fixed::quantize_n_bits(1).unwrap(),
+    );
+    let fixed = Img::new(buf.into_iter().map(f64::from), width).unwrap();
+
+    let (float_err, fixed_err) = (
+        perceptual_error(&gray, &float),
+        perceptual_error(&gray, &fixed),
+    );
+    assert!(
+        (fixed_err - float_err).abs() < float_err * 0.05,
+        "{} vs {}",
+        fixed_err,
+        float_err
+    );
+}
+
+#[test]
+fn test_dither_fixed_golden_output() {
+    use crate::ditherer::fixed::{self, FixedDitherer};
+    use crate::ditherer::{ATKINSON, FLOYD_STEINBERG, STUCKI};
+    let dither = |kernel: &FixedDitherer, buf: &[u8], width: usize, channels: usize, bits: u8| {
+        let mut buf = buf.to_vec();
+        let mut scratch = vec![0; kernel.scratch_len(width, channels)];
+        let quantize = fixed::quantize_n_bits(bits).unwrap();
+        kernel.dither(&mut buf, width, channels, &mut scratch, quantize);
+        buf
+    };
+    // the outputs must match byte for byte on every platform.
+    #[rustfmt::skip]
+    let gray: [u8; 32] = [
+        0, 29, 58, 87, 116, 145, 174, 203,
+        71, 113, 155, 197, 239, 25, 67, 109,
+        142, 197, 252, 51, 106, 161, 216, 15,
+        213, 25, 93, 161, 229, 41, 109, 177,
+    ];
+    let (floyd, atkinson, stucki) = (
+        FLOYD_STEINBERG.to_fixed().unwrap(),
+        ATKINSON.to_fixed().unwrap(),
+        STUCKI.to_fixed().unwrap(),
+    );
+    #[rustfmt::skip]
+    let tt: [(&FixedDitherer, u8, [u8; 32]); 4] = [
+        (&floyd, 1, [
+            0, 0, 0, 0, 255, 0, 255, 255,
+            0, 255, 255, 255, 255, 0, 0, 0,
+            255, 0, 255, 0, 0, 255, 255, 0,
+            255, 0, 0, 255, 255, 0, 0, 255,
+        ]),
+        (&floyd, 2, [
+            0, 0, 128, 0, 128, 128, 128, 255,
+            128, 128, 128, 255, 255, 0, 128, 128,
+            128, 128, 255, 0, 128, 128, 255, 0,
+            255, 0, 128, 128, 255, 0, 128, 128,
+        ]),
+        (&atkinson, 1, [
+            0, 0, 0, 0, 255, 255, 255, 255,
+            0, 255, 255, 255, 255, 0, 0, 0,
+            255, 255, 255, 0, 0, 255, 255, 0,
+            255, 0, 0, 255, 255, 0, 0, 255,
+        ]),
+        (&stucki, 1, [
+            0, 0, 0, 0, 255, 255, 255, 255,
+            0, 255, 255, 255, 255, 0, 0, 0,
+            255, 255, 255, 0, 0, 255, 255, 0,
+            255, 0, 0, 255, 255, 0, 0, 255,
+        ]),
+    ];
+    for (kernel, bits, want) in tt {
+        assert_eq!(
+            dither(kernel, &gray, 8, 1, bits),
+            want,
+            "{:?} at {} bits",
+            kernel,
+            bits
+        );
+    }
+
+    // 3 interleaved channels, 4 pixels to a row.
+    let rgb: Vec<u8> = (0..36).map(|i| ((i * 97 + 31) % 256) as u8).collect();
+    #[rustfmt::skip]
+    let want = [
+        0, 255, 255, 0, 0, 0, 255, 255, 0, 0, 255, 0,
+        255, 0, 0, 255, 0, 255, 255, 0, 255, 0, 255, 255,
+        0, 255, 255, 0, 255, 0, 255, 255, 0, 0, 0, 0,
+    ];
+    assert_eq!(dither(&floyd, &rgb, 4, 3, 1), want);
+
+    // a flat field, where truncating each tap used to lose error and shift the pattern.
+    #[rustfmt::skip]
+    let want = [
+        0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 255, 0, 0, 255, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 255, 0, 0, 255, 0, 0,
+    ];
+    assert_eq!(dither(&floyd, &[43; 32], 8, 1, 1), want);
+
+    // a divisor at the i16::MAX cap, and weights that add up to all of it.
+    let wide = FixedDitherer::new(i32::from(i16::MAX), &[(1, 0, 16384), (2, 0, 16383)]);
+    assert_eq!(
+        dither(&wide, &[100, 255, 0, 200, 30, 255, 128, 10], 8, 1, 1),
+        [0, 255, 0, 255, 0, 255, 255, 0]
+    );
+
+    // kernels that spread more error than there is would amplify it until it overflowed, so they're rejected.
+    use crate::ditherer::Ditherer;
+    assert!(Ditherer::new(1., &[(1, 0, 32767.)]).to_fixed().is_none());
+    assert!(Ditherer::new(16., &[(1, 0, 9.), (0, 1, -8.)])
+        .to_fixed()
+        .is_none());
+    assert!(std::panic::catch_unwind(|| FixedDitherer::new(1, &[(1, 0, 32767)])).is_err());
+}
+
+#[test]
+fn test_dither_fixed_matches_reference() {
+    use crate::ditherer::fixed::{self, FixedDitherer, FRACTION_BITS};
+    // FixedDitherer::dither written the obvious way: a whole image of error, every share divided with `/`,
+    // and every tap checked against the edges.
+    let reference = |div: i32,
+                     offsets: &[(isize, isize, i32)],
+                     buf: &mut [u8],
+                     width: usize,
+                     channels: usize| {
+        let quantize = fixed::quantize_n_bits(2).unwrap();
+        let height = buf.len() / (width * channels);
+        let mut errors = vec![0i64; buf.len()];
+        let total: i64 = offsets.iter().map(|&(_, _, mul)| i64::from(mul)).sum();
+        for y in 0..height {
+            for x in 0..width {
+                for c in 0..channels {
+                    let i = (y * width + x) * channels + c;
+                    let (quantized, spill) =
+                        quantize((i32::from(buf[i]) << FRACTION_BITS) + errors[i] as i32);
+                    buf[i] = quantized;
+                    let spill = i64::from(spill);
+                    let mut remaining = spill * total / i64::from(div);
+                    for (t, &(dx, dy, mul)) in offsets.iter().enumerate() {
+                        let share = if t + 1 == offsets.len() {
+                            remaining
+                        } else {
+                            spill * i64::from(mul) / i64::from(div)
+                        };
+                        remaining -= share;
+                        let (nx, ny) = (x as isize + dx, y + dy as usize);
+                        if nx >= 0 && (nx as usize) < width && ny < height {
+                            errors[(ny * width + nx as usize) * channels + c] += share;
+                        }
+                    }
+                }
+            }
+        }
+    };
+    type Kernel<'a> = (i32, &'a [(isize, isize, i32)]);
+    let kernels: [Kernel; 4] = [
+        (16, &[(1, 0, 7), (-1, 1, 3), (0, 1, 5), (1, 1, 1)]),
+        // a kernel that reaches further than the image is wide.
+        (
+            37,
+            &[
+                (1, 0, 9),
+                (4, 0, 3),
+                (-5, 1, 6),
+                (2, 1, 7),
+                (-1, 3, 5),
+                (6, 2, 4),
+            ],
+        ),
+        (-23, &[(1, 0, -11), (0, 1, -7), (-2, 1, 2), (3, 2, -3)]),
+        (
+            30000,
+            &[(1, 0, 13001), (-1, 1, 7002), (0, 1, 5999), (1, 1, 3998)],
+        ),
+    ];
+    let img: Vec<u8> = (0..300u32).map(|i| (i * 97 % 256) as u8).collect();
+    for (div, offsets) in kernels {
+        let kernel = FixedDitherer::new(div, offsets);
+        for (width, channels) in [(5, 1), (20, 3), (4, 3), (100, 1)] {
+            let len = img.len() / (width * channels) * width * channels;
+            let mut want = img[..len].to_vec();
+            reference(div, offsets, &mut want, width, channels);
+            let mut got = img[..len].to_vec();
+            let mut scratch = vec![0; kernel.scratch_len(width, channels)];
+            let quantize = fixed::quantize_n_bits(2).unwrap();
+            kernel.dither(&mut got, width, channels, &mut scratch, quantize);
+            assert_eq!(got, want, "{} {:?} at {}x{}", div, offsets, width, channels);
+        }
+    }
+}
+
+#[test]
+fn test_dither_compact_representations() {
+    use crate::ditherer::ATKINSON;
+    let quantize = crate::create_quantize_n_bits_func(1).unwrap();
+    let gradient = |x: u32| (x % 256) as u8;
+    let mean = |img: Img<f64>| img.iter().sum::<f64>() / img.len() as f64;
+
+    let wide = Img::new((0..4096).map(|x| f64::from(gradient(x))), 64).unwrap();
+    let want = mean(ATKINSON.dither(wide, &quantize));
+
+    let compact = Img::new((0..4096).map(|x| f32::from(gradient(x))), 64).unwrap();
+    let got = ATKINSON.dither(compact, |x: f32| {
+        let (quot, rem) = quantize(f64::from(x));
+        (quot as f32, rem as f32)
+    });
+    assert!((mean(got.convert_with(f64::from)) - want).abs() < 1.);
+
+    let integer = Img::new((0..4096).map(|x| i16::from(gradient(x))), 64).unwrap();
+    let got = ATKINSON.dither(integer, |x: i16| {
+        let (quot, rem) = quantize(f64::from(x));
+        (quot as i16, rem.round() as i16)
+    });
+    assert!(got.iter().all(|&p| p == 0 || p == 255));
+    assert!((mean(got.convert_with(f64::from)) - want).abs() < 1.);
+}
+
+#[test]
+fn test_more_than_u32_max_pixels() {
+    // zero-sized pixels, so the buffers cost nothing.
+    type Nothing = [f32; 0];
+    #[allow(clippy::uninit_vec)]
+    fn huge(len: usize) -> Vec<Nothing> {
+        let mut buf = Vec::new();
+        // SAFETY: an empty array is zero-sized, so any length is valid and initialized.
+        unsafe { buf.set_len(len) };
+        buf
+    }
+
+    let (width, height) = (100_000_u32, 50_000_u32);
+    let img = Img::from_vec(huge(width as usize * height as usize), width).unwrap();
+    assert!(img.len() > u32::MAX as usize);
+    assert_eq!(img.size(), (width, height));
+    assert_eq!(img.get((width - 1, height - 1)), Some(&[]));
+    assert_eq!(img.get((0, height)), None);
+    assert_eq!(Img::from_vec(huge(u32::MAX as usize + 1), 1), None);
+
+    // three 4-pixel rows, each more than u32::MAX pixels apart.
+    let stride = 1 << 33;
+    let mut buf = huge(2 * stride + 4);
+    let mut scratch = vec![[]; crate::ditherer::STUCKI.scratch_len(4)];
+    let mut quantized = 0;
+    crate::ditherer::STUCKI.dither_strided(&mut buf, 4, stride, &mut scratch, |p| {
+        quantized += 1;
+        (p, [])
+    });
+    assert_eq!(quantized, 12);
+}
+
+#[test]
+fn test_registry_matches_built_in_ditherers() {
+    use crate::ditherer::registry::{self, Registry};
+    let registry = Registry::default();
+    for entry in registry.entries() {
+        for name in std::iter::once(entry.name()).chain(entry.aliases().iter().map(String::as_str))
+        {
+            let selected = registry.select(name).unwrap();
+            let want: Ditherer = name.parse().unwrap();
+            assert_eq!(selected.kernel(), Some(want), "{}", name);
+            assert_eq!(selected.to_string(), entry.name());
+        }
+    }
+    assert_eq!(
+        registry.select("floyd:strength=2").unwrap_err(),
+        registry::Error::UnknownParameter {
+            algorithm: "floyd".to_string(),
+            parameter: "strength".to_string()
+        }
+    );
+    assert_eq!(
+        registry.select("nope").unwrap_err(),
+        registry::Error::UnknownAlgorithm("nope".to_string())
+    );
+}
+
+#[test]
+fn test_position_aware_quantizer() {
+    use crate::ditherer::*;
+    use crate::quantize::{BitDepth, Quantizer};
+    /// 1-bit on even columns, 2-bit on odd ones.
+    #[derive(Clone)]
+    struct Columns;
+    impl Quantizer<f64> for Columns {
+        fn nearest(&mut self, p: &f64, (x, _): (usize, usize)) -> f64 {
+            BitDepth::new(1 + (x % 2) as u8).unwrap().level(*p)
+        }
+        fn error(&mut self, p: f64, nearest: &f64, _: (usize, usize)) -> f64 {
+            p - nearest
+        }
+    }
+    let img: Img<f64> = Img::new((0..400).map(|x| f64::from(x % 200)), 20).unwrap();
+    let got = ATKINSON.dither(img.clone(), Columns);
+    for y in 0..got.height() {
+        for x in 0..got.width() {
+            let levels: &[f64] = if x % 2 == 0 {
+                &[0., 255.]
+            } else {
+                &[0., 127.5, 255.]
+            };
+            assert!(levels.contains(&got[(x, y)]), "({}, {})", x, y);
+        }
+    }
+    assert_eq!(got, ATKINSON.dither_parallel(img, Columns, 4));
+}
+
+#[test]
+fn test_channels_dither_independently() {
+    use crate::color::CMYK;
+    use crate::ditherer::*;
+    use crate::quantize::PerChannel;
+    let quantize = crate::create_quantize_n_bits_func(1).unwrap();
+    let (width, len) = (16, 16 * 12);
+    let plane =
+        |i: usize| -> Vec<f64> { (0..len).map(|x| ((x * (i + 3) * 7) % 256) as f64).collect() };
+    let planes: Vec<Vec<f64>> = (0..5)
+        .map(|i| {
+            STUCKI
+                .dither(Img::new(plane(i), width).unwrap(), &quantize)
+                .into_vec()
+        })
+        .collect();
+
+    let spectral = Img::new(
+        (0..len).map(|x| std::array::from_fn(|i| plane(i)[x])),
+        width,
+    )
+    .unwrap();
+    let got: Img<[f64; 5]> = STUCKI.dither_parallel(spectral, PerChannel(&quantize), 3);
+    for (x, p) in got.iter().enumerate() {
+        assert_eq!(p.to_vec(), (0..5).map(|i| planes[i][x]).collect::<Vec<_>>());
+    }
+
+    let separations = Img::new(
+        (0..len).map(|x| CMYK::from_channels(|i| plane(i)[x])),
+        width,
+    )
+    .unwrap();
+    let got = STUCKI.dither(separations, PerChannel(&quantize));
+    for (x, p) in got.iter().enumerate() {
+        assert_eq!(
+            *p,
+            CMYK(planes[0][x], planes[1][x], planes[2][x], planes[3][x])
+        );
+    }
+}
+
+#[test]
+fn test_audio_noise_shaping() {
+    use crate::ditherer::audio::{self, Noise, Shaper};
+    let quantize = audio::quantize_bits(8).unwrap();
+    let signal: Vec<f64> = (0..44_100)
+        .map(|n| 0.25 * f64::sin(n as f64 / 40.))
+        .collect();
+    // the power of the error from 100Hz to 4kHz (at 44.1kHz), where hearing is most sensitive.
+    let audible_error = |filter: &'static [f64]| {
+        let mut samples = signal.clone();
+        Shaper::new(filter, Noise::Triangular(audio::lsb(8))).process(&mut samples, &quantize);
+        let error: Vec<f64> = samples.iter().zip(&signal).map(|(y, x)| y - x).collect();
+        (1..=40)
+            .map(|band| {
+                let w = 2. * std::f64::consts::PI * f64::from(band) * 100. / 44_100.;
+                let (re, im) = error.iter().enumerate().fold((0., 0.), |(re, im), (n, e)| {
+                    (
+                        re + e * f64::cos(w * n as f64),
+                        im + e * f64::sin(w * n as f64),
+                    )
+                });
+                re * re + im * im
+            })
+            .sum::<f64>()
+    };
+    let flat = audible_error(audio::FLAT);
+    for filter in [audio::LIPSHITZ, audio::F_WEIGHTED] {
+        assert!(audible_error(filter) < flat / 4., "{:?}", filter);
+    }
+}
+
+#[test]
+fn test_sequence_keeps_static_regions() {
+    use crate::ditherer::sequence::Sequence;
+    use crate::ditherer::*;
+    let quantize = crate::create_quantize_n_bits_func(1).unwrap();
+    let background = load_test_image().convert_with(|rgb| {
+        rgb.convert_with(f64::from)
+            .to_chroma_corrected_black_and_white()
+    });
+    // a square moving right across the top-left corner.
+    let frame = |i: u32| {
+        let mut img = background.clone();
+        for y in 10..30 {
+            for x in 10 + 5 * i..30 + 5 * i {
+                img[(x, y)] = 255.;
+            }
+        }
+        img
+    };
+    let moving = |x: u32, y: u32| (10..30).contains(&y) && (10..40).contains(&x);
+
+    let mut sequence = Sequence::new(0.);
+    let mut outputs = (0..2).map(|i| {
+        sequence.dither(frame(i), &quantize, |img, q| {
+            FLOYD_STEINBERG.dither_parallel(img, q, 4)
+        })
+    });
+    let (first, second) = (outputs.next().unwrap(), outputs.next().unwrap());
+    assert_eq!(first, FLOYD_STEINBERG.dither(frame(0), &quantize));
+    for y in 0..first.height() {
+        for x in 0..first.width() {
+            if !moving(x, y) {
+                assert_eq!(first[(x, y)], second[(x, y)], "({}, {})", x, y);
+            }
+        }
+    }
+    // without it, the whole image boils.
+    let independent = FLOYD_STEINBERG.dither(frame(1), &quantize);
+    let boiled = (0..first.height())
+        .flat_map(|y| (0..first.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| !moving(x, y) && first[(x, y)] != independent[(x, y)])
+        .count();
+    assert!(boiled > 100, "{}", boiled);
+}
+
+#[test]
+fn test_dither_in_working_space() {
+    use crate::color::palette::{self, cga};
+    use crate::color::space::{InSpace, Space};
+    use crate::ditherer::*;
+    let img = load_test_image().convert_with(|rgb| rgb.convert_with(f64::from));
+    let to_u8 =
+        |img: Img<RGB<f64>>| img.convert_with(|rgb| rgb.convert_with(crate::clamp_f64_to_u8));
+
+    // sRGB is the identity: the same as dithering the image directly.
+    let srgb = InSpace::new(Space::Srgb, palette::quantize(cga::ALL));
+    assert_eq!(
+        Space::Srgb.convert_back(FLOYD_STEINBERG.dither(Space::Srgb.convert(img.clone()), srgb)),
+        FLOYD_STEINBERG.dither(img.clone(), palette::quantize(cga::ALL)),
+    );
+    for space in [Space::Lab, Space::Oklab] {
+        let quantize = InSpace::new(space, palette::quantize_with(cga::ALL, space.distance()));
+        let got = FLOYD_STEINBERG.dither(space.convert(img.clone()), quantize.clone());
+        assert_eq!(
+            got,
+            FLOYD_STEINBERG.dither_parallel(space.convert(img.clone()), quantize, 4)
+        );
+        let got = to_u8(space.convert_back(got));
+        assert!(got.iter().all(|p| cga::ALL.contains(p)), "{:?}", space);
+    }
+
+    // what the request is about: on a muted green gradient, sRGB error diffusion drifts the hue of the dithered
+    // output, seen from far enough away to blend each 8x8 block in linear light, further than Lab or Oklab do.
+    let (width, height) = (128, 64);
+    let img = Img::new(
+        (0..width * height).map(|i| {
+            let (x, y) = (f64::from(i % width), f64::from(i / width));
+            RGB(30. + y * 0.8, 60. + x * 1.4, 20.)
+        }),
+        width,
+    )
+    .unwrap();
+    let to_linear = |c: f64| {
+        let c = c / 255.;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let from_linear = |c: f64| {
+        255. * if c <= 0.003_130_8 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1. / 2.4) - 0.055
+        }
+    };
+    let block_hues = |img: &Img<RGB<f64>>| -> Vec<f64> {
+        let mut hues = Vec::new();
+        for (bx, by) in (0..height / 8).flat_map(|by| (0..width / 8).map(move |bx| (bx, by))) {
+            let mut sum = [0.; 3];
+            for (x, y) in
+                (by * 8..by * 8 + 8).flat_map(|y| (bx * 8..bx * 8 + 8).map(move |x| (x, y)))
+            {
+                let RGB(r, g, b) = img[(x, y)];
+                for (sum, c) in sum.iter_mut().zip([r, g, b]) {
+                    *sum += to_linear(c) / 64.;
+                }
+            }
+            let [_, a, b] = Space::Oklab.from_rgb(&RGB(
+                from_linear(sum[0]),
+                from_linear(sum[1]),
+                from_linear(sum[2]),
+            ));
+            hues.push(f64::atan2(b, a));
+        }
+        hues
+    };
+    let want = block_hues(&img);
+    let drift = |got: Img<RGB<f64>>| -> f64 {
+        let got = block_hues(&got);
+        let total: f64 = want
+            .iter()
+            .zip(&got)
+            .map(|(want, got)| {
+                let d = (want - got).abs();
+                d.min(std::f64::consts::TAU - d)
+            })
+            .sum();
+        total / want.len() as f64
+    };
+    let srgb_drift = drift(FLOYD_STEINBERG.dither(img.clone(), palette::quantize(cga::ALL)));
+    for space in [Space::Lab, Space::Oklab] {
+        let quantize = InSpace::new(space, palette::quantize_with(cga::ALL, space.distance()));
+        let got = space.convert_back(FLOYD_STEINBERG.dither(space.convert(img.clone()), quantize));
+        let space_drift = drift(got);
+        assert!(
+            space_drift < srgb_drift,
+            "{:?}: {} >= {}",
+            space,
+            space_drift,
+            srgb_drift
+        );
+    }
+}
+
+#[test]
+fn test_in_space_spreads_no_error_only_where_solid() {
+    use crate::color::palette::{self, cga};
+    use crate::color::space::{InSpace, Space};
+    use crate::quantize::Quantizer;
+    // a solid CGA blue pair next to a pair of grays, which are dithered.
+    let img: Img<RGB<f64>> = Img::new(
+        [
+            RGB(0., 0., 170.),
+            RGB(0., 0., 170.),
+            RGB(100., 100., 100.),
+            RGB(140., 140., 140.),
+        ],
+        4,
+    )
+    .unwrap();
+    let mut quantize = InSpace::new(Space::Oklab, palette::quantize_flat(&img, cga::ALL, 0.));
+    for (x, solid) in [(0, true), (1, true), (2, false), (3, false)] {
+        assert_eq!(
+            Quantizer::<[f64; 3]>::is_solid(&quantize, (x, 0)),
+            solid,
+            "{}",
+            x
+        );
+        let p = Space::Oklab.from_rgb(&img[(x as u32, 0)]);
+        let (_, error) = quantize.quantize(p, (x, 0));
+        assert_eq!(error == [0.; 3], solid, "{}", x);
+    }
+
+    // a quantizer that reports no error isn't solid: the error is still spread in the working space.
+    let mut quantize = InSpace::new(Space::Oklab, |_: RGB<f64>| {
+        (RGB(0., 0., 0.), RGB(0., 0., 0.))
+    });
+    let p = Space::Oklab.from_rgb(&RGB(100., 100., 100.));
+    assert!(!Quantizer::<[f64; 3]>::is_solid(&quantize, (0, 0)));
+    assert_ne!(quantize.quantize(p, (0, 0)).1, [0.; 3]);
+}
+
+#[test]
+fn test_optimize_never_makes_the_score_worse() {
+    use crate::ditherer::{optimize, ATKINSON, FLOYD_STEINBERG};
+    use std::num::NonZeroU32;
+    let training = [
+        Img::new((0..256).map(|x| f64::from(x % 32) * 8.), 32).unwrap(),
+        Img::new((0..256).map(|x| f64::from(x / 16) * 16.), 16).unwrap(),
+    ];
+    let quantize = crate::create_quantize_n_bits_func(1).unwrap();
+    for start in [FLOYD_STEINBERG, ATKINSON] {
+        let start_score = optimize::score(&start, &training, &quantize);
+        assert!(start_score.is_finite() && start_score > 0.);
+        for (resolution, seed) in [(1, 1), (2, 7), (4, 42)] {
+            let resolution = NonZeroU32::new(resolution).unwrap();
+            let (kernel, score) =
+                optimize::optimize(&start, &training, &quantize, 30, resolution, seed);
+            assert!(
+                score <= start_score,
+                "{} at {}x: {} > {}",
+                start,
+                resolution,
+                score,
+                start_score
+            );
+            assert_eq!(
+                score,
+                optimize::score(&kernel.ditherer(), &training, &quantize)
+            );
+            assert!(kernel.div > 0.);
+        }
+    }
+}
+
+#[test]
+fn test_row_ditherer_matches_parallel_and_golden_output() {
+    use crate::ditherer::*;
+    let quantize = crate::create_quantize_n_bits_func(1).unwrap();
+    let stream = |ditherer: &Ditherer, img: &Img<f64>| {
+        let width = img.width() as usize;
+        let mut stream = RowDitherer::new(ditherer, width, &quantize);
+        let mut rows: Vec<Vec<f64>> = img
+            .clone()
+            .into_vec()
+            .chunks(width)
+            .map(<[f64]>::to_vec)
+            .collect();
+        for row in rows.iter_mut() {
+            stream.push_row(row);
+            assert_eq!(stream.buffered_len(), (ditherer.reach() + 1) * width);
+        }
+        assert_eq!(stream.rows_done(), rows.len());
+        rows.concat()
+    };
+
+    let img = Img::new(
+        (0..24).map(|i| f64::from((i % 6 * 37 + i / 6 * 53) % 256)),
+        6,
+    )
+    .unwrap();
+    #[rustfmt::skip]
+    let want = vec![
+        0., 0., 0., 255., 255., 255.,
+        0., 0., 255., 0., 255., 255.,
+        0., 255., 255., 255., 255., 0.,
+        255., 255., 255., 0., 0., 0.,
+    ];
+    assert_eq!(stream(&STUCKI, &img), want);
+
+    let bunny = load_test_image().convert_with(|rgb| {
+        rgb.convert_with(f64::from)
+            .to_chroma_corrected_black_and_white()
+    });
+    for ditherer in [
+        FLOYD_STEINBERG,
+        ATKINSON,
+        STUCKI,
+        JARVIS_JUDICE_NINKE,
+        SIERRA_3,
+    ] {
+        let want = ditherer
+            .dither_parallel(bunny.clone(), &quantize, 4)
+            .into_vec();
+        assert_eq!(stream(&ditherer, &bunny), want, "{}", ditherer);
+    }
+}
+
+#[test]
+fn test_progress_and_cancel_through_dyn_dither() {
+    use crate::ditherer::registry::{Algorithm, DynDither};
+    use crate::ditherer::{Cancel, STUCKI};
+    use crate::quantize::{CloneQuantizer, Quantizer};
+    use std::sync::Mutex;
+    /// an algorithm without a kernel, so it gets the default progress reporting.
+    #[derive(Debug)]
+    struct Posterize;
+    impl<P: Pixel> DynDither<P> for Posterize {
+        fn dither_dyn(&self, img: Img<P>, quantize: &mut dyn Quantizer<P>) -> Img<P> {
+            let width = img.width() as usize;
+            let mut i = 0;
+            img.convert_with(|p| {
+                let at = (i % width, i / width);
+                i += 1;
+                quantize.quantize(p, at).0
+            })
+        }
+    }
+    impl Algorithm for Posterize {}
+
+    let img: Img<f64> = Img::new((0..4000).map(|x| f64::from(x % 255)), 40).unwrap();
+    let quantize: &dyn CloneQuantizer<f64> = &crate::quantize::BitDepth::new(1).unwrap();
+    let algorithms: [&dyn Algorithm; 2] = [&STUCKI, &Posterize];
+    for algorithm in algorithms {
+        for threads in [1, 4] {
+            let reported = Mutex::new(Vec::new());
+            let progress = |done, total| reported.lock().unwrap().push((done, total));
+            let got = algorithm.dither_with_progress_dyn(
+                img.clone(),
+                quantize,
+                &progress,
+                &Cancel::new(),
+                threads,
+            );
+            assert_eq!(
+                got.unwrap(),
+                algorithm.dither_parallel_dyn(img.clone(), quantize, threads)
+            );
+            let reported = reported.into_inner().unwrap();
+            assert!(
+                reported.windows(2).all(|w| w[0].0 < w[1].0),
+                "{:?}",
+                reported
+            );
+            assert_eq!(reported.last(), Some(&(100, 100)));
+
+            let cancel = Cancel::new();
+            cancel.cancel();
+            let got = algorithm.dither_with_progress_dyn(
+                img.clone(),
+                quantize,
+                &|_, _| {},
+                &cancel,
+                threads,
+            );
+            assert!(matches!(got, Err(Error::Cancelled)));
+        }
+    }
+
+    // cancelled partway through: the kernel stops early, and reports no more rows.
+    let cancel = Cancel::new();
+    let reported = Mutex::new(0);
+    let progress = |done, _| {
+        *reported.lock().unwrap() = done;
+        if done == 10 {
+            cancel.cancel();
+        }
+    };
+    let got = STUCKI.dither_with_progress_dyn(img, quantize, &progress, &cancel, 1);
+    assert!(matches!(got, Err(Error::Cancelled)));
+    assert_eq!(reported.into_inner().unwrap(), 10);
+}
+
+#[test]
+fn test_temporal_frames_average_to_the_input() {
+    use crate::ditherer::{temporal, FLOYD_STEINBERG};
+    let quantize = crate::create_quantize_n_bits_func(1).unwrap();
+    let img: Img<f64> = Img::new((0..256).map(f64::from), 16).unwrap();
+    let average = |frames: &[Img<f64>], i: usize| {
+        frames
+            .iter()
+            .map(|frame| frame.iter().nth(i).unwrap())
+            .sum::<f64>()
+            / frames.len() as f64
+    };
+    for n in [1, 2, 4, 8, 16] {
+        // without spatial dithering, what a pixel is owed never passes half a level, so each pixel's average
+        // is within half a level / n of its input.
+        let no_op = crate::ditherer::Ditherer::new(1., &[]);
+        let frames = temporal::dither_frames(&img, n, |frame| no_op.dither(frame, &quantize));
+        assert_eq!(frames.len(), n);
+        for (i, &p) in img.iter().enumerate() {
+            assert!(
+                (average(&frames, i) - p).abs() <= 127.5 / n as f64,
+                "{} frames, pixel {}",
+                n,
+                i
+            );
+        }
+
+        // spatial error moves intensity between neighbors, and some falls off the edges of each frame,
+        // but the frames owe it back, so the mean brightness converges on the input's.
+        let frames =
+            temporal::dither_frames(&img, n, |frame| FLOYD_STEINBERG.dither(frame, &quantize));
+        let want = img.iter().sum::<f64>() / img.len() as f64;
+        let got = (0..img.len()).map(|i| average(&frames, i)).sum::<f64>() / img.len() as f64;
+        assert!(
+            (got - want).abs() < 4. / n as f64,
+            "{} frames: {} vs {}",
+            n,
+            got,
+            want
+        );
+    }
+}
+
+#[test]
+fn test_temporal_pack_layout() {
+    use crate::ditherer::temporal::{bits_per_level, level, pack};
+    assert_eq!(
+        (1..=7).map(bits_per_level).collect::<Vec<_>>(),
+        [1, 2, 2, 3, 3, 3, 3]
+    );
+    assert_eq!([0, 85, 170, 255].map(|v| level(v, 3)), [0, 1, 2, 3]);
+    // 1 bit: most significant bit first, and each row padded out to a whole byte.
+    assert_eq!(
+        pack(
+            &[1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0],
+            9,
+            1
+        ),
+        [0xFF, 0x80, 0x40, 0x00]
+    );
+    // 2 bits at a depth of 3.
+    assert_eq!(
+        pack(&[1, 2, 3, 0, 3, 2, 1], 7, 3),
+        [0b0110_1100, 0b1110_0100]
+    );
+    // 3 bits at a depth of 4, straddling bytes.
+    assert_eq!(
+        pack(&[4, 1, 7, 2, 0, 4], 3, 4),
+        [0b1000_0111, 0b1000_0000, 0b0100_0010, 0]
+    );
+    assert!(pack(&[], 3, 1).is_empty());
+}
+
+#[test]
+fn test_edges_detectors_and_thickness() {
+    use crate::edges::{self, Detector};
+    // a dark square in the middle of a light field.
+    let (width, height) = (16, 16);
+    let square = |x: u32, y: u32| (4..12).contains(&x) && (4..12).contains(&y);
+    let luma = Img::new(
+        (0..width * height).map(|i| {
+            if square(i % width, i / width) {
+                30.
+            } else {
+                220.
+            }
+        }),
+        width,
+    )
+    .unwrap();
+    let count = |lines: &Img<bool>| lines.iter().filter(|&&edge| edge).count();
+
+    for detector in [Detector::Sobel, Detector::Canny] {
+        let thin = edges::detect(&luma, detector, 64., 1);
+        // the edges hug the square's border, and nothing far from it is marked.
+        assert!(thin[(4, 8)] || thin[(3, 8)], "{:?}", detector);
+        assert!(thin[(11, 8)] || thin[(12, 8)], "{:?}", detector);
+        for (x, y) in [(0, 0), (15, 15), (8, 8), (0, 8)] {
+            assert!(!thin[(x, y)], "{:?} at ({}, {})", detector, x, y);
+        }
+        // too high a threshold finds nothing, and a thickness of 0 draws nothing.
+        assert_eq!(count(&edges::detect(&luma, detector, 1000., 1)), 0);
+        assert_eq!(count(&edges::detect(&luma, detector, 64., 0)), 0);
+        // thicker lines cover everything thinner ones do, and more.
+        let mut previous = thin;
+        for thickness in 2..=4 {
+            let thick = edges::detect(&luma, detector, 64., thickness);
+            assert!(previous.iter().zip(thick.iter()).all(|(&p, &t)| !p || t));
+            assert!(
+                count(&thick) > count(&previous),
+                "{:?} at {}",
+                detector,
+                thickness
+            );
+            previous = thick;
+        }
+    }
+    // canny's lines are thinner than sobel's.
+    assert!(
+        count(&edges::detect(&luma, Detector::Canny, 64., 1))
+            < count(&edges::detect(&luma, Detector::Sobel, 64., 1))
+    );
+}
+
+#[test]
+fn test_subset_of_one_color_is_rejected() {
+    // like `--color=auto:1`, before the input is even loaded.
+    let opts = Opt {
+        input: "bunny.png".into(),
+        output: Some("bunny_subset_1.png".into()),
+        color_mode: "cga".parse().unwrap(),
+        subset: std::num::NonZeroUsize::new(1),
+        ..Opt::default()
+    };
+    assert!(matches!(
+        crate::cli::run(&opts),
+        Err(Error::Color(color::Error::PaletteTooSmall))
+    ));
+    assert!(!std::path::Path::new("bunny_subset_1.png").exists());
+}
+
+#[test]
+fn test_subpixel_keeps_flat_colors() {
+    use crate::color::subpixel::Stripe;
+    // each sub-pixel's error stays in its own channel, so a flat color keeps its mean in every channel, to within
+    // the error dropped off the edges, in both pipelines and for either stripe order. the samples of a flat
+    // color are all the same, so it comes out just like dithering each channel without sub-pixels.
+    for (i, color) in [RGB(40, 160, 40), RGB(200, 30, 0), RGB(90, 90, 200)]
+        .into_iter()
+        .enumerate()
+    {
+        let input = std::env::current_dir()
+            .unwrap()
+            .join(format!("subpixel_flat_{}.png", i));
+        let output = input.with_file_name(format!("subpixel_flat_{}_dithered.png", i));
+        Img::new(vec![color; 64 * 64], 64)
+            .unwrap()
+            .save(&input)
+            .unwrap();
+        for (stripe, fixed) in [Stripe::RGB, Stripe::BGR]
+            .into_iter()
+            .flat_map(|stripe| [(stripe, false), (stripe, true)])
+        {
+            let opts = Opt {
+                input: input.clone(),
+                output: Some(output.clone()),
+                color_mode: "color".parse().unwrap(),
+                bit_depth: 1,
+                subpixel: Some(stripe),
+                fixed,
+                ..Opt::default()
+            };
+            crate::cli::run(&opts).unwrap();
+            let got = Img::<RGB<u8>>::load(&output).unwrap();
+            crate::cli::run(&Opt {
+                subpixel: None,
+                ..opts
+            })
+            .unwrap();
+            assert_eq!(got, Img::load(&output).unwrap());
+            let mut sum = [0.; 3];
+            for RGB(r, g, b) in got.iter().cloned() {
+                for (sum, c) in sum.iter_mut().zip([r, g, b]) {
+                    *sum += f64::from(c) / got.len() as f64;
+                }
+            }
+            let RGB(r, g, b) = color;
+            for (mean, want) in sum.into_iter().zip([r, g, b]) {
+                assert!(
+                    (mean - f64::from(want)).abs() <= 2.,
+                    "{:?} with {:?}, fixed: {}: got a mean of {:?}",
+                    color,
+                    stripe,
+                    fixed,
+                    sum
+                );
+            }
+        }
+        std::fs::remove_file(input).unwrap();
+        std::fs::remove_file(output).unwrap();
+    }
+}